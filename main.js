@@ -2,7 +2,7 @@
 // Integrates Web Audio API with Rust WASM audio processing
 
 // Initialize WASM module
-import init, { AudioProcessor } from './pkg/audio_dsp_wasm.js';
+import init, { AudioProcessor, ProcessorParams, FilterType, FilterSlope, StereoDelayMode, SpectrumScale, DetectorMode, WaveshaperCurve, EffectKind } from './pkg/audio_dsp_wasm.js';
 
 // Global state
 let wasmModule = null;
@@ -13,6 +13,9 @@ let sourceNode = null;
 let processorNode = null;
 let isAudioRunning = false;
 
+// Handles of the preset's modulation effects (chorus, phaser, ring modulator...)
+let modulationIds = [];
+
 // Visualizer state
 let analyser = null;
 let visualizerCanvas = null;
@@ -22,6 +25,11 @@ let animationId = null;
 let dataArray = null;
 let bufferLength = 0;
 
+// Spectrum of the processed output, in dBFS per band (from the Rust analyzer)
+let spectrum = null;
+let spectrumPeaks = null;
+const SPECTRUM_FLOOR_DB = -90;
+
 // Performance monitoring
 let performanceStats = {
     lastTime: performance.now(),
@@ -38,9 +46,22 @@ let params = {
     lpfCutoff: 20000,
     hpfCutoff: 20,
     distortion: 0.0,
+    distortionCurve: 'SoftClip',
     delayTime: 0.0,
     delayFeedback: 0.0,
-    delayMix: 0.0
+    delayMix: 0.0,
+    delayMode: 'Stereo',
+    delayOffset: 0.0,
+    delayCross: 0.0,
+    delayWidth: 1.0,
+    lpfSlope: 'Db12',
+    hpfSlope: 'Db12',
+    eq: [],
+    reverb: null,
+    compressor: null,
+    modulation: [],
+    // Noise gate at the head of the Rust chain (replaces the old -60 dB JS gate)
+    gate: { threshold: -60, hysteresis: 6, attack: 1, hold: 50, release: 150, range: -80, sidechainHpf: 80 }
 };
 
 // Initialize application
@@ -73,36 +94,43 @@ function setupAudioControls() {
     // Parameter sliders
     setupSlider('gainSlider', 'gainValue', value => {
         params.gain = value / 100;
+        if (audioProcessor) audioProcessor.set_gain(params.gain);
         return params.gain.toFixed(2);
     });
     
     setupSlider('lpfSlider', 'lpfValue', value => {
         params.lpfCutoff = value;
+        if (audioProcessor) audioProcessor.set_lpf_cutoff(value);
         return value + ' Hz';
     });
     
     setupSlider('hpfSlider', 'hpfValue', value => {
         params.hpfCutoff = value;
+        if (audioProcessor) audioProcessor.set_hpf_cutoff(value);
         return value + ' Hz';
     });
     
     setupSlider('distortionSlider', 'distortionValue', value => {
         params.distortion = value / 100;
+        if (audioProcessor) audioProcessor.set_distortion(params.distortion);
         return params.distortion.toFixed(2);
     });
     
     setupSlider('delayTimeSlider', 'delayTimeValue', value => {
         params.delayTime = value / 1000; // Convert ms to seconds
+        if (audioProcessor) audioProcessor.set_delay_time(params.delayTime);
         return value + ' ms';
     });
     
     setupSlider('delayFeedbackSlider', 'delayFeedbackValue', value => {
         params.delayFeedback = value / 100;
+        if (audioProcessor) audioProcessor.set_delay_feedback(params.delayFeedback);
         return params.delayFeedback.toFixed(2);
     });
     
     setupSlider('delayMixSlider', 'delayMixValue', value => {
         params.delayMix = value / 100;
+        if (audioProcessor) audioProcessor.set_delay_mix(params.delayMix);
         return params.delayMix.toFixed(2);
     });
 }
@@ -130,6 +158,10 @@ async function startAudio() {
         
         // Create Rust audio processor
         audioProcessor = new AudioProcessor(sampleRate);
+        audioProcessor.set_channels(2);
+        syncParams();
+        syncModulation();
+        configureSpectrum();
         
         // Request microphone access with noise suppression
         updateStatus('Requesting microphone access...');
@@ -148,11 +180,11 @@ async function startAudio() {
         // Create ScriptProcessorNode for real-time processing
         // Buffer size: 4096 samples (balance between latency and performance)
         const bufferSize = 4096;
-        processorNode = audioContext.createScriptProcessor(bufferSize, 1, 1);
+        processorNode = audioContext.createScriptProcessor(bufferSize, 2, 2);
         
         // Display buffer info
         document.getElementById('bufferSize').textContent = bufferSize.toLocaleString() + ' samples';
-        const dataSize = (bufferSize * 4 / 1024).toFixed(1); // 4 bytes per f32
+        const dataSize = (bufferSize * 2 * 4 / 1024).toFixed(1); // 2 channels, 4 bytes per f32
         document.getElementById('dataPerCall').textContent = dataSize + ' KB';
         
         // Display sample rate
@@ -160,41 +192,29 @@ async function startAudio() {
         
         // Audio processing callback - this runs in real-time!
         processorNode.onaudioprocess = (e) => {
-            const inputBuffer = e.inputBuffer.getChannelData(0);
-            const outputBuffer = e.outputBuffer.getChannelData(0);
+            // A mono microphone is up-mixed to both input channels
+            const outputLeft = e.outputBuffer.getChannelData(0);
+            const outputRight = e.outputBuffer.getChannelData(1);
             
-            // Copy input to output and apply simple noise gate
-            const noiseGateThreshold = 0.001; // -60dB
-            for (let i = 0; i < inputBuffer.length; i++) {
-                // Simple noise gate: cut off very quiet signals
-                if (Math.abs(inputBuffer[i]) < noiseGateThreshold) {
-                    outputBuffer[i] = 0.0;
-                } else {
-                    outputBuffer[i] = inputBuffer[i];
-                }
-            }
+            // Copy input to output - Rust processes the output buffers in place
+            // (noise gating is the first stage of the Rust chain)
+            outputLeft.set(e.inputBuffer.getChannelData(0));
+            outputRight.set(e.inputBuffer.getChannelData(1));
             
             // Process audio with Rust/WASM
             // This is where the magic happens - high-performance DSP in Rust!
             const startTime = performance.now();
             
-            audioProcessor.process(
-                outputBuffer,
-                params.gain,
-                params.lpfCutoff,
-                params.hpfCutoff,
-                params.delayTime,
-                params.delayFeedback,
-                params.delayMix,
-                params.distortion
-            );
+            // Parameters live on the processor - only the buffers are sent
+            // Each channel keeps its own filter and delay state
+            audioProcessor.process_stereo(outputLeft, outputRight);
             
             // Track performance
             performanceStats.processingTime = performance.now() - startTime;
             performanceStats.audioCallbacks++;
             
-            // Track total data sent to Rust (4096 samples × 4 bytes per sample)
-            performanceStats.totalDataProcessed += outputBuffer.length * 4;
+            // Track total data sent to Rust (2 × 4096 samples × 4 bytes per sample)
+            performanceStats.totalDataProcessed += (outputLeft.length + outputRight.length) * 4;
         };
         
         // Connect audio graph: Microphone -> Processor -> Speakers
@@ -228,6 +248,11 @@ function stopAudio() {
             processorNode = null;
         }
         
+        if (analyser) {
+            analyser.disconnect();
+            analyser = null;
+        }
+        
         if (sourceNode) {
             sourceNode.disconnect();
             sourceNode = null;
@@ -249,6 +274,7 @@ function stopAudio() {
         if (audioProcessor) {
             audioProcessor.reset();
             audioProcessor = null;
+            modulationIds = [];
         }
         
         // Update UI
@@ -270,6 +296,95 @@ function stopAudio() {
     }
 }
 
+// Push the full parameter set to the Rust processor
+function syncParams() {
+    if (!audioProcessor) return;
+    
+    const processorParams = new ProcessorParams();
+    processorParams.gain = params.gain;
+    processorParams.lpf_cutoff = params.lpfCutoff;
+    processorParams.hpf_cutoff = params.hpfCutoff;
+    processorParams.distortion = params.distortion;
+    processorParams.distortion_curve = WaveshaperCurve[params.distortionCurve];
+    processorParams.delay_time = params.delayTime;
+    processorParams.delay_feedback = params.delayFeedback;
+    processorParams.delay_mix = params.delayMix;
+    processorParams.delay_mode = StereoDelayMode[params.delayMode];
+    processorParams.delay_offset = params.delayOffset;
+    processorParams.delay_cross_feedback = params.delayCross;
+    processorParams.delay_width = params.delayWidth;
+    processorParams.lpf_slope = FilterSlope[params.lpfSlope];
+    processorParams.hpf_slope = FilterSlope[params.hpfSlope];
+    
+    const gate = params.gate;
+    processorParams.gate_threshold = gate.threshold;
+    processorParams.gate_hysteresis = gate.hysteresis;
+    processorParams.gate_attack = gate.attack;
+    processorParams.gate_hold = gate.hold;
+    processorParams.gate_release = gate.release;
+    processorParams.gate_range = gate.range;
+    processorParams.gate_sidechain_hpf = gate.sidechainHpf;
+    
+    // Reverb is off unless the preset asks for it
+    const reverb = params.reverb || { mix: 0.0 };
+    processorParams.reverb_mix = reverb.mix;
+    if (reverb.roomSize !== undefined) processorParams.reverb_room_size = reverb.roomSize;
+    if (reverb.decay !== undefined) processorParams.reverb_decay = reverb.decay;
+    if (reverb.damping !== undefined) processorParams.reverb_damping = reverb.damping;
+    if (reverb.preDelay !== undefined) processorParams.reverb_pre_delay = reverb.preDelay;
+    if (reverb.diffusion !== undefined) processorParams.reverb_diffusion = reverb.diffusion;
+    
+    // Compressor runs at 1:1 (no compression) unless the preset sets it up
+    const compressor = params.compressor || { ratio: 1.0 };
+    processorParams.compressor_ratio = compressor.ratio;
+    if (compressor.threshold !== undefined) processorParams.compressor_threshold = compressor.threshold;
+    if (compressor.knee !== undefined) processorParams.compressor_knee = compressor.knee;
+    if (compressor.attack !== undefined) processorParams.compressor_attack = compressor.attack;
+    if (compressor.release !== undefined) processorParams.compressor_release = compressor.release;
+    if (compressor.makeup !== undefined) processorParams.compressor_makeup = compressor.makeup;
+    if (compressor.detector !== undefined) processorParams.compressor_detector = DetectorMode[compressor.detector];
+    
+    audioProcessor.set_params(processorParams);
+    processorParams.free();
+    
+    // Parametric EQ bands: existing ones glide to their new settings,
+    // only the surplus is added or removed
+    const bandCount = audioProcessor.eq_band_count();
+    params.eq.forEach((band, index) => {
+        if (index < bandCount) {
+            audioProcessor.eq_set_band(index, FilterType[band.type], band.frequency, band.gain, band.q);
+            audioProcessor.eq_set_band_enabled(index, true);
+        } else {
+            audioProcessor.eq_add_band(FilterType[band.type], band.frequency, band.gain, band.q);
+        }
+    });
+    for (let index = bandCount - 1; index >= params.eq.length; index--) {
+        audioProcessor.eq_remove_band(index);
+    }
+}
+
+// Rebuild the preset's modulation effects, each just ahead of the
+// built-in stage named by `before` (the delay unless given)
+// Only done when the preset changes - a fresh effect restarts its LFO
+function syncModulation() {
+    if (!audioProcessor) return;
+    
+    for (const id of modulationIds) {
+        audioProcessor.remove_effect(id);
+    }
+    modulationIds = [];
+    
+    for (const modulation of params.modulation) {
+        const beforeId = audioProcessor.builtin_effect_id(EffectKind[modulation.before || 'StereoDelay']);
+        const index = Array.from(audioProcessor.effect_ids()).indexOf(beforeId);
+        const id = audioProcessor.insert_effect(index, EffectKind[modulation.kind]);
+        for (const [name, value] of Object.entries(modulation.params)) {
+            audioProcessor.set_effect_param(id, name, value);
+        }
+        modulationIds.push(id);
+    }
+}
+
 // Update status display
 function updateStatus(message, isActive = false) {
     const statusElement = document.getElementById('audioStatus');
@@ -282,7 +397,8 @@ function updateStatus(message, isActive = false) {
     }
 }
 
-// Professional preset configurations (CLEAN - no feedback noise!)
+// Professional preset configurations
+// Delay feedback is the true loop gain and delay mix uses an equal-power law
 const presets = {
     clean: {
         gain: 1.0,
@@ -297,19 +413,29 @@ const presets = {
         gain: 1.3,
         lpfCutoff: 3400,
         hpfCutoff: 300,
+        lpfSlope: 'Db48',    // Steep band-limiting
+        hpfSlope: 'Db48',
         distortion: 0.15,
         delayTime: 0.0,
         delayFeedback: 0.0,
-        delayMix: 0.0
+        delayMix: 0.0,
+        eq: [
+            { type: 'Peaking', frequency: 1800, gain: 6, q: 1.2 }   // Nasal handset resonance
+        ]
     },
     radio: {
         gain: 1.4,
         lpfCutoff: 4500,
         hpfCutoff: 250,
+        lpfSlope: 'Db24',    // Steep band-limiting
+        hpfSlope: 'Db24',
         distortion: 0.35,
+        distortionCurve: 'Tube',
         delayTime: 0.0,
         delayFeedback: 0.0,
-        delayMix: 0.0
+        delayMix: 0.0,
+        // Heavy broadcast squash
+        compressor: { threshold: -24, ratio: 8, knee: 3, attack: 2, release: 80, makeup: 8 }
     },
     space: {
         gain: 0.85,
@@ -317,26 +443,39 @@ const presets = {
         hpfCutoff: 20,
         distortion: 0.0,
         delayTime: 0.4,
-        delayFeedback: 0.4,  // Reduced from 0.5
-        delayMix: 0.3        // Reduced from 0.35
+        delayFeedback: 0.1,
+        delayMix: 0.11,
+        delayMode: 'PingPong',
+        // Slow shimmer drifting between the speakers
+        modulation: [
+            { kind: 'Phaser', params: { stages: 6, rate: 0.1, frequency: 1200, depth: 2, feedback: 0.4, stereo: 90, mix: 0.4 } }
+        ]
     },
     robot: {
         gain: 1.1,
         lpfCutoff: 6000,
         hpfCutoff: 150,
         distortion: 0.5,
+        distortionCurve: 'Foldback',
         delayTime: 0.07,
-        delayFeedback: 0.2,  // Reduced from 0.25
-        delayMix: 0.15       // Reduced from 0.2
+        delayFeedback: 0.05,
+        delayMix: 0.05,
+        // Lowered, formant-kept voice under a 30 Hz Dalek carrier
+        modulation: [
+            { kind: 'PitchShifter', before: 'Distortion', params: { semitones: -5, formant: 1 } },
+            { kind: 'RingModulator', before: 'LowPass', params: { frequency: 30, mix: 0.85 } }
+        ]
     },
     cave: {
         gain: 0.9,
         lpfCutoff: 10000,
         hpfCutoff: 80,
         distortion: 0.0,
-        delayTime: 0.28,
-        delayFeedback: 0.45, // Reduced from 0.55
-        delayMix: 0.35       // Reduced from 0.4
+        delayTime: 0.0,
+        delayFeedback: 0.0,
+        delayMix: 0.0,
+        // Dark, dense rock walls
+        reverb: { mix: 0.35, roomSize: 0.6, decay: 2.5, damping: 0.6, preDelay: 0.01, diffusion: 0.5 }
     },
     valley: {
         gain: 0.8,
@@ -344,8 +483,10 @@ const presets = {
         hpfCutoff: 50,       // Remove low rumble
         distortion: 0.0,     // Clean echo
         delayTime: 0.65,     // Long delay like shouting in a valley
-        delayFeedback: 0.5,  // Multiple repeats fading away
-        delayMix: 0.4        // Clear echo effect
+        delayFeedback: 0.12, // Multiple repeats fading away
+        delayMix: 0.16,      // Clear echo effect
+        delayOffset: 0.09,   // Far wall answers a little later
+        delayCross: 0.35     // Echoes bounce between the valley sides
     },
     stadium: {
         gain: 1.2,
@@ -353,17 +494,26 @@ const presets = {
         hpfCutoff: 100,
         distortion: 0.05,    // Slight presence boost
         delayTime: 0.45,     // Big space feeling
-        delayFeedback: 0.35,
-        delayMix: 0.3
+        delayFeedback: 0.09,
+        delayMix: 0.11,
+        delayMode: 'PingPong',
+        delayWidth: 0.8,     // Echoes sweep across the stands
+        reverb: { mix: 0.25, roomSize: 1.0, decay: 3.5, damping: 0.5, preDelay: 0.06, diffusion: 0.7 }
     },
     alien: {
         gain: 0.95,
         lpfCutoff: 2500,     // Weird narrow bandwidth
         hpfCutoff: 400,
         distortion: 0.25,    // Distorted alien sound
+        distortionCurve: 'SineFold',
         delayTime: 0.12,     // Fast metallic echo
-        delayFeedback: 0.4,
-        delayMix: 0.35
+        delayFeedback: 0.1,
+        delayMix: 0.13,
+        // Fast warbling voices
+        modulation: [
+            { kind: 'PitchShifter', before: 'Distortion', params: { semitones: 7 } },
+            { kind: 'Chorus', params: { voices: 4, rate: 4.5, depth: 2.5, delay: 8, spread: 1.0, mix: 0.6 } }
+        ]
     },
     underwater: {
         gain: 0.7,
@@ -371,14 +521,17 @@ const presets = {
         hpfCutoff: 20,
         distortion: 0.0,
         delayTime: 0.55,     // Slow dreamy echo
-        delayFeedback: 0.45,
-        delayMix: 0.4
+        delayFeedback: 0.11,
+        delayMix: 0.16
     },
     walkietalkie: {
         gain: 1.4,
         lpfCutoff: 2800,     // Narrow radio bandwidth
         hpfCutoff: 400,
+        lpfSlope: 'Db36',    // Steep band-limiting
+        hpfSlope: 'Db36',
         distortion: 0.3,     // Radio distortion
+        distortionCurve: 'HardClip',
         delayTime: 0.0,
         delayFeedback: 0.0,
         delayMix: 0.0
@@ -388,9 +541,11 @@ const presets = {
         lpfCutoff: 16000,
         hpfCutoff: 40,
         distortion: 0.0,
-        delayTime: 0.38,     // Natural hall reverb
-        delayFeedback: 0.4,
-        delayMix: 0.25
+        delayTime: 0.0,
+        delayFeedback: 0.0,
+        delayMix: 0.0,
+        // Natural hall reverb
+        reverb: { mix: 0.3, roomSize: 0.9, decay: 2.2, damping: 0.4, preDelay: 0.03, diffusion: 0.8 }
     },
     ghost: {
         gain: 0.75,
@@ -398,8 +553,14 @@ const presets = {
         hpfCutoff: 200,
         distortion: 0.15,
         delayTime: 0.33,     // Spooky echo timing
-        delayFeedback: 0.5,
-        delayMix: 0.45
+        delayFeedback: 0.12,
+        delayMix: 0.18,
+        // Wavering voice that drifts around the room
+        modulation: [
+            { kind: 'PitchShifter', before: 'Distortion', params: { semitones: -3, formant: 1, mix: 0.5 } },
+            { kind: 'Tremolo', params: { rate: 6, depth: 0.35 } },
+            { kind: 'AutoPan', params: { rate: 0.2, depth: 0.7 } }
+        ]
     },
     podcast: {
         gain: 1.1,
@@ -408,7 +569,14 @@ const presets = {
         distortion: 0.02,    // Subtle warmth
         delayTime: 0.0,
         delayFeedback: 0.0,
-        delayMix: 0.0
+        delayMix: 0.0,
+        eq: [
+            { type: 'LowShelf', frequency: 150, gain: 2, q: 0.707 },  // Body
+            { type: 'Peaking', frequency: 350, gain: -3, q: 1.0 },    // Less boxiness
+            { type: 'Peaking', frequency: 3500, gain: 3, q: 0.9 }     // Presence
+        ],
+        // Gentle levelling for spoken word
+        compressor: { threshold: -20, ratio: 3, knee: 8, attack: 15, release: 200, makeup: 5, detector: 'Rms' }
     },
     psychedelic: {
         gain: 0.8,
@@ -416,8 +584,14 @@ const presets = {
         hpfCutoff: 60,
         distortion: 0.2,
         delayTime: 0.48,     // Trippy timing
-        delayFeedback: 0.55, // Heavy repeats
-        delayMix: 0.5        // Very wet
+        delayFeedback: 0.14, // Heavy repeats
+        delayMix: 0.2,       // Very wet
+        modulation: [
+            // Deep 8-stage sweep ahead of the filters, circling across the image
+            { kind: 'Phaser', before: 'LowPass', params: { stages: 8, rate: 0.3, frequency: 900, depth: 2.5, feedback: 0.6, stereo: 180, mix: 0.5 } },
+            // Slow through-zero jet sweep
+            { kind: 'Flanger', params: { rate: 0.15, depth: 0.8, manual: 0.5, feedback: 0.6, through_zero: 1, mix: 0.5 } }
+        ]
     }
 };
 
@@ -444,19 +618,29 @@ function setupPresets() {
 
 // Apply preset to sliders
 function applyPreset(preset) {
-    // Clear delay buffer when switching presets to prevent noise
-    if (audioProcessor) {
-        audioProcessor.reset();
-    }
-    
     // Update parameters
     params.gain = preset.gain;
     params.lpfCutoff = preset.lpfCutoff;
     params.hpfCutoff = preset.hpfCutoff;
     params.distortion = preset.distortion;
+    params.distortionCurve = preset.distortionCurve || 'SoftClip';
     params.delayTime = preset.delayTime;
     params.delayFeedback = preset.delayFeedback;
     params.delayMix = preset.delayMix;
+    params.delayMode = preset.delayMode || 'Stereo';
+    params.delayOffset = preset.delayOffset || 0.0;
+    params.delayCross = preset.delayCross || 0.0;
+    params.delayWidth = preset.delayWidth ?? 1.0;
+    params.lpfSlope = preset.lpfSlope || 'Db12';
+    params.hpfSlope = preset.hpfSlope || 'Db12';
+    params.eq = preset.eq || [];
+    params.reverb = preset.reverb || null;
+    params.compressor = preset.compressor || null;
+    params.modulation = preset.modulation || [];
+    
+    // The processor glides to the new settings, so no reset is needed
+    syncParams();
+    syncModulation();
     
     // Update sliders
     document.getElementById('gainSlider').value = preset.gain * 100;
@@ -507,6 +691,26 @@ function setVisualizerMode(mode) {
     // Update button states
     document.querySelectorAll('.btn-viz').forEach(btn => btn.classList.remove('active'));
     document.getElementById('viz' + mode.charAt(0).toUpperCase() + mode.slice(1)).classList.add('active');
+    
+    configureSpectrum();
+}
+
+// Fine log bands for the spectrum view, 1/3 octaves for the bar view
+function configureSpectrum() {
+    if (!audioProcessor) return;
+    
+    if (visualizerMode === 'bars') {
+        audioProcessor.set_spectrum_scale(SpectrumScale.ThirdOctave);
+    } else {
+        audioProcessor.set_spectrum_scale(SpectrumScale.Logarithmic);
+        audioProcessor.set_spectrum_bands(128);
+    }
+}
+
+// Height of a dBFS level on a canvas, SPECTRUM_FLOOR_DB at the bottom
+function dbToHeight(db, height) {
+    const t = (db - SPECTRUM_FLOOR_DB) / -SPECTRUM_FLOOR_DB;
+    return Math.min(Math.max(t, 0), 1) * height;
 }
 
 function startVisualizer() {
@@ -519,10 +723,8 @@ function startVisualizer() {
         bufferLength = analyser.frequencyBinCount;
         dataArray = new Uint8Array(bufferLength);
         
-        // Connect: source -> analyser -> processor
-        sourceNode.disconnect();
-        sourceNode.connect(analyser);
-        analyser.connect(processorNode);
+        // Tap the processed signal: processor -> analyser
+        processorNode.connect(analyser);
     }
     
     // Start animation
@@ -534,12 +736,10 @@ function animate() {
     
     animationId = requestAnimationFrame(animate);
     
-    // Get audio data
-    if (visualizerMode === 'waveform' || visualizerMode === 'circle') {
-        analyser.getByteTimeDomainData(dataArray);
-    } else {
-        analyser.getByteFrequencyData(dataArray);
-    }
+    // Waveform from the analyser, spectrum from Rust - both after processing
+    analyser.getByteTimeDomainData(dataArray);
+    spectrum = audioProcessor.analyze_spectrum();
+    spectrumPeaks = audioProcessor.spectrum_peaks();
     
     // Draw based on mode
     switch (visualizerMode) {
@@ -620,19 +820,23 @@ function drawSpectrum() {
     visualizerCtx.fillStyle = '#0f172a';
     visualizerCtx.fillRect(0, 0, width, height);
     
-    // Draw spectrum
-    const barWidth = width / bufferLength * 2.5;
-    let x = 0;
+    // Draw spectrum (log-spaced bands, 20 Hz - 20 kHz)
+    const bands = spectrum.length;
+    const barWidth = width / bands;
     
-    for (let i = 0; i < bufferLength; i++) {
-        const barHeight = (dataArray[i] / 255) * height;
+    for (let i = 0; i < bands; i++) {
+        const barHeight = dbToHeight(spectrum[i], height);
+        const x = i * barWidth;
         
         // Color based on frequency
-        const hue = (i / bufferLength) * 360;
+        const hue = (i / bands) * 360;
         visualizerCtx.fillStyle = `hsl(${hue}, 70%, 60%)`;
+        visualizerCtx.fillRect(x, height - barHeight, barWidth - 1, barHeight);
         
-        visualizerCtx.fillRect(x, height - barHeight, barWidth, barHeight);
-        x += barWidth + 1;
+        // Peak hold marker
+        const peakY = height - dbToHeight(spectrumPeaks[i], height);
+        visualizerCtx.fillStyle = `hsl(${hue}, 70%, 80%)`;
+        visualizerCtx.fillRect(x, peakY, barWidth - 1, 2);
     }
 }
 
@@ -647,12 +851,12 @@ function drawBars() {
     visualizerCtx.fillStyle = bgGradient;
     visualizerCtx.fillRect(0, 0, width, height);
     
-    const bars = 64;
+    // One bar per 1/3-octave band
+    const bars = spectrum.length;
     const barWidth = width / bars;
     
     for (let i = 0; i < bars; i++) {
-        const dataIndex = Math.floor(i * bufferLength / bars);
-        const barHeight = (dataArray[dataIndex] / 255) * height * 0.8;
+        const barHeight = dbToHeight(spectrum[i], height * 0.8);
         
         // Gradient bar
         const barGradient = visualizerCtx.createLinearGradient(0, height, 0, height - barHeight);
@@ -715,18 +919,11 @@ function updateStats() {
     // Calculate peak
     let peak = 0;
     let sum = 0;
-    let maxFreqIndex = 0;
-    let maxFreqValue = 0;
     
     for (let i = 0; i < bufferLength; i++) {
         const value = Math.abs((dataArray[i] - 128) / 128.0);
         peak = Math.max(peak, value);
         sum += value * value;
-        
-        if (dataArray[i] > maxFreqValue && i < bufferLength / 2) {
-            maxFreqValue = dataArray[i];
-            maxFreqIndex = i;
-        }
     }
     
     // RMS
@@ -736,8 +933,8 @@ function updateStats() {
     const peakDb = peak > 0 ? 20 * Math.log10(peak) : -Infinity;
     const rmsDb = rms > 0 ? 20 * Math.log10(rms) : -Infinity;
     
-    // Dominant frequency
-    const freq = maxFreqIndex * audioContext.sampleRate / analyser.fftSize;
+    // Dominant frequency of the processed output
+    const freq = audioProcessor.dominant_frequency();
     
     // Update audio stats display
     document.getElementById('peakLevel').textContent = 
@@ -746,6 +943,8 @@ function updateStats() {
         rmsDb === -Infinity ? '-∞ dB' : rmsDb.toFixed(1) + ' dB';
     document.getElementById('dominantFreq').textContent = 
         freq > 0 ? Math.round(freq) + ' Hz' : '-- Hz';
+    document.getElementById('gainReduction').textContent = 
+        audioProcessor.compressor_gain_reduction().toFixed(1) + ' dB';
     
     // Update audio callbacks counter
     document.getElementById('audioCallbacks').textContent = 
@@ -1,12 +0,0 @@
-/* tslint:disable */
-/* eslint-disable */
-export const memory: WebAssembly.Memory;
-export const __wbg_audioprocessor_free: (a: number, b: number) => void;
-export const audioprocessor_get_buffer_size: (a: number) => number;
-export const audioprocessor_get_memory_usage: (a: number) => number;
-export const audioprocessor_new: (a: number) => number;
-export const audioprocessor_process: (a: number, b: number, c: number, d: any, e: number, f: number, g: number, h: number, i: number, j: number, k: number) => void;
-export const audioprocessor_reset: (a: number) => void;
-export const __wbindgen_externrefs: WebAssembly.Table;
-export const __wbindgen_malloc: (a: number, b: number) => number;
-export const __wbindgen_start: () => void;
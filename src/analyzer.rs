@@ -0,0 +1,418 @@
+use wasm_bindgen::prelude::*;
+
+use crate::fft::{Complex, RealFft};
+use crate::window::{coherent_gain, fill_window, WindowType};
+
+pub const DEFAULT_FFT_SIZE: usize = 4096;
+pub const FFT_SIZE_RANGE: (usize, usize) = (256, 32768);
+pub const DEFAULT_BAND_COUNT: usize = 64;
+pub const BAND_COUNT_RANGE: (usize, usize) = (1, 1024);
+
+// Everything quieter reads as this level
+pub const MIN_DB: f32 = -120.0;
+
+// How the spectrum is grouped into display bands
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpectrumScale {
+    // Equal-width bands
+    Linear,
+    // Equal ratio between band edges
+    Logarithmic,
+    // ISO 1/3-octave bands (band count is fixed by the frequency range)
+    ThirdOctave,
+}
+
+impl SpectrumScale {
+    pub fn from_index(index: usize) -> Option<SpectrumScale> {
+        [SpectrumScale::Linear, SpectrumScale::Logarithmic, SpectrumScale::ThirdOctave]
+            .get(index)
+            .copied()
+    }
+}
+
+// Display band: FFT bins [start, end) plus the centre as a fractional bin
+// for bands narrower than one bin
+#[derive(Clone, Copy, Debug)]
+struct Band {
+    centre: f32,
+    start: usize,
+    end: usize,
+    centre_bin: f32,
+}
+
+// Spectrum analyzer for metering and display
+//
+// Audio is pushed into a ring buffer holding the last fft_size samples, which
+// costs one copy per sample; the FFT only runs when analyze() is called (once
+// per animation frame). Each band reads the loudest bin inside it, so a sine
+// shows its amplitude in dBFS whatever the band width. Levels rise with the
+// attack time and fall with the release time, measured in audio time between
+// analyses; peaks hold for peak_hold ms and then fall at peak_decay dB/s.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct SpectrumAnalyzer {
+    sample_rate: f32,
+    fft_size: usize,
+    window_type: WindowType,
+    scale: SpectrumScale,
+    band_count: usize,
+    min_frequency: f32,
+    max_frequency: f32,
+    attack_ms: f32,
+    release_ms: f32,
+    peak_hold_ms: f32,
+    peak_decay: f32,
+    fft: RealFft,
+    window: Vec<f32>,
+    history: Vec<f32>,
+    pos: usize,
+    // Samples pushed since the last analysis
+    pending: usize,
+    frame: Vec<f32>,
+    spectrum: Vec<Complex>,
+    // Per-bin linear amplitude of the last analysis
+    amplitudes: Vec<f32>,
+    bands: Vec<Band>,
+    levels: Vec<f32>,
+    peaks: Vec<f32>,
+    // Seconds since each peak was set
+    peak_ages: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl SpectrumAnalyzer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32) -> SpectrumAnalyzer {
+        let mut analyzer = SpectrumAnalyzer {
+            sample_rate,
+            fft_size: DEFAULT_FFT_SIZE,
+            window_type: WindowType::BlackmanHarris,
+            scale: SpectrumScale::Logarithmic,
+            band_count: DEFAULT_BAND_COUNT,
+            min_frequency: 20.0,
+            max_frequency: 20000.0,
+            attack_ms: 10.0,
+            release_ms: 300.0,
+            peak_hold_ms: 1000.0,
+            peak_decay: 20.0,
+            fft: RealFft::new(2),
+            window: Vec::new(),
+            history: Vec::new(),
+            pos: 0,
+            pending: 0,
+            frame: Vec::new(),
+            spectrum: Vec::new(),
+            amplitudes: Vec::new(),
+            bands: Vec::new(),
+            levels: Vec::new(),
+            peaks: Vec::new(),
+            peak_ages: Vec::new(),
+        };
+        analyzer.rebuild_fft();
+        analyzer
+    }
+
+    // Rounded up to a power of two
+    pub fn set_fft_size(&mut self, size: usize) {
+        let size = size.clamp(FFT_SIZE_RANGE.0, FFT_SIZE_RANGE.1).next_power_of_two();
+        if size != self.fft_size {
+            self.fft_size = size;
+            self.rebuild_fft();
+        }
+    }
+
+    pub fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    pub fn set_window(&mut self, window: WindowType) {
+        if window != self.window_type {
+            self.window_type = window;
+            self.rebuild_fft();
+        }
+    }
+
+    pub fn set_scale(&mut self, scale: SpectrumScale) {
+        if scale != self.scale {
+            self.scale = scale;
+            self.rebuild_bands();
+        }
+    }
+
+    pub fn scale(&self) -> SpectrumScale {
+        self.scale
+    }
+
+    // Bands for the linear and logarithmic scales
+    pub fn set_band_count(&mut self, count: usize) {
+        let count = count.clamp(BAND_COUNT_RANGE.0, BAND_COUNT_RANGE.1);
+        if count != self.band_count {
+            self.band_count = count;
+            self.rebuild_bands();
+        }
+    }
+
+    // Number of values magnitudes_db() returns for the current scale
+    pub fn band_count(&self) -> usize {
+        self.bands.len()
+    }
+
+    // Displayed range in Hz, limited to 1 Hz .. Nyquist
+    pub fn set_frequency_range(&mut self, min: f32, max: f32) {
+        if !min.is_finite() || !max.is_finite() {
+            return;
+        }
+        let nyquist = self.sample_rate * 0.5;
+        let min = min.clamp(1.0, nyquist);
+        let max = max.clamp(1.0, nyquist);
+        if max > min {
+            self.min_frequency = min;
+            self.max_frequency = max;
+            self.rebuild_bands();
+        }
+    }
+
+    // Time for a rising level to cover ~63% of the step, in ms
+    pub fn set_attack(&mut self, time_ms: f32) {
+        if time_ms.is_finite() {
+            self.attack_ms = time_ms.max(0.0);
+        }
+    }
+
+    // Time for a falling level to cover ~63% of the step, in ms
+    pub fn set_release(&mut self, time_ms: f32) {
+        if time_ms.is_finite() {
+            self.release_ms = time_ms.max(0.0);
+        }
+    }
+
+    pub fn set_peak_hold(&mut self, time_ms: f32) {
+        if time_ms.is_finite() {
+            self.peak_hold_ms = time_ms.max(0.0);
+        }
+    }
+
+    // Fall rate of a released peak in dB per second
+    pub fn set_peak_decay(&mut self, db_per_second: f32) {
+        if db_per_second.is_finite() {
+            self.peak_decay = db_per_second.max(0.0);
+        }
+    }
+
+    // Feed mono audio
+    pub fn push(&mut self, samples: &[f32]) {
+        for &x in samples {
+            self.write(x);
+        }
+    }
+
+    // Run the FFT over the latest fft_size samples and advance the ballistics
+    pub fn analyze(&mut self) {
+        let n = self.fft_size;
+        let (older, newer) = self.history.split_at(self.pos);
+        self.frame[..newer.len()].copy_from_slice(newer);
+        self.frame[newer.len()..].copy_from_slice(older);
+        for (x, &w) in self.frame.iter_mut().zip(self.window.iter()) {
+            *x *= w;
+        }
+        self.fft.forward(&self.frame, &mut self.spectrum);
+
+        let scale = 2.0 / (n as f32 * coherent_gain(self.window_type));
+        for (a, c) in self.amplitudes.iter_mut().zip(self.spectrum.iter()) {
+            *a = c.abs() * scale;
+        }
+
+        let dt = self.pending as f32 / self.sample_rate;
+        self.pending = 0;
+        let attack = ballistics_coeff(dt, self.attack_ms);
+        let release = ballistics_coeff(dt, self.release_ms);
+        let hold = self.peak_hold_ms * 0.001;
+
+        for (i, band) in self.bands.iter().enumerate() {
+            let db = amplitude_db(band_amplitude(band, &self.amplitudes));
+            let level = &mut self.levels[i];
+            let coeff = if db > *level { attack } else { release };
+            *level = db + (*level - db) * coeff;
+
+            let (peak, age) = (&mut self.peaks[i], &mut self.peak_ages[i]);
+            if *level >= *peak {
+                *peak = *level;
+                *age = 0.0;
+            } else {
+                // Only the part of this step past the hold time decays
+                let falling = (*age + dt - hold).clamp(0.0, dt);
+                *age += dt;
+                *peak = (*peak - self.peak_decay * falling).max(*level);
+            }
+        }
+    }
+
+    // Smoothed band levels in dBFS, low to high
+    pub fn magnitudes_db(&self) -> Vec<f32> {
+        self.levels.clone()
+    }
+
+    // Held peak levels in dBFS, one per band
+    pub fn peaks_db(&self) -> Vec<f32> {
+        self.peaks.clone()
+    }
+
+    // Centre frequency of each band in Hz
+    pub fn frequencies(&self) -> Vec<f32> {
+        self.bands.iter().map(|b| b.centre).collect()
+    }
+
+    // Frequency of the loudest bin in the displayed range at the last analysis,
+    // refined by parabolic interpolation; 0 when everything is below MIN_DB
+    pub fn dominant_frequency(&self) -> f32 {
+        let bin_width = self.sample_rate / self.fft_size as f32;
+        let last = self.amplitudes.len() - 1;
+        let start = ((self.min_frequency / bin_width).ceil() as usize).clamp(1, last);
+        let end = ((self.max_frequency / bin_width).floor() as usize).clamp(start, last);
+        let Some((k, &peak)) = self.amplitudes[start..=end]
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+        else {
+            return 0.0;
+        };
+        if amplitude_db(peak) <= MIN_DB {
+            return 0.0;
+        }
+        let k = start + k;
+        let (a, b, c) = (
+            amplitude_db(self.amplitudes[k - 1]),
+            amplitude_db(peak),
+            amplitude_db(self.amplitudes[(k + 1).min(last)]),
+        );
+        let denom = a - 2.0 * b + c;
+        let offset = if denom.abs() > 1e-9 { (0.5 * (a - c) / denom).clamp(-0.5, 0.5) } else { 0.0 };
+        (k as f32 + offset) * bin_width
+    }
+
+    pub fn reset(&mut self) {
+        self.history.fill(0.0);
+        self.amplitudes.fill(0.0);
+        self.levels.fill(MIN_DB);
+        self.peaks.fill(MIN_DB);
+        self.peak_ages.fill(0.0);
+        self.pos = 0;
+        self.pending = 0;
+    }
+}
+
+impl SpectrumAnalyzer {
+    // Feed planar audio (`channels` equal runs) as the average of all channels
+    pub fn push_planar(&mut self, buffer: &[f32], channels: usize) {
+        let channels = channels.max(1);
+        let len = buffer.len() / channels;
+        let scale = 1.0 / channels as f32;
+        for i in 0..len {
+            let sum: f32 = (0..channels).map(|c| buffer[c * len + i]).sum();
+            self.write(sum * scale);
+        }
+    }
+
+    fn write(&mut self, x: f32) {
+        self.history[self.pos] = if x.is_finite() { x } else { 0.0 };
+        self.pos += 1;
+        if self.pos == self.fft_size {
+            self.pos = 0;
+        }
+        self.pending = self.pending.saturating_add(1);
+    }
+
+    fn rebuild_fft(&mut self) {
+        let n = self.fft_size;
+        self.fft = RealFft::new(n);
+        self.window = vec![0.0; n];
+        fill_window(self.window_type, &mut self.window);
+        self.history = vec![0.0; n];
+        self.frame = vec![0.0; n];
+        self.spectrum = vec![Complex::ZERO; self.fft.bins()];
+        self.amplitudes = vec![0.0; self.fft.bins()];
+        self.pos = 0;
+        self.pending = 0;
+        self.rebuild_bands();
+    }
+
+    fn rebuild_bands(&mut self) {
+        let (lo, hi) = (self.min_frequency, self.max_frequency);
+        let edges: Vec<(f32, f32, f32)> = match self.scale {
+            SpectrumScale::Linear => {
+                let n = self.band_count;
+                let width = (hi - lo) / n as f32;
+                (0..n)
+                    .map(|i| {
+                        let start = lo + width * i as f32;
+                        (start, start + width * 0.5, start + width)
+                    })
+                    .collect()
+            }
+            SpectrumScale::Logarithmic => {
+                let n = self.band_count;
+                let ratio = (hi / lo).powf(1.0 / n as f32);
+                (0..n)
+                    .map(|i| {
+                        let start = lo * ratio.powi(i as i32);
+                        (start, start * ratio.sqrt(), start * ratio)
+                    })
+                    .collect()
+            }
+            SpectrumScale::ThirdOctave => {
+                // Centres at 1 kHz * 2^(k/3), edges a sixth of an octave either side
+                let first = (3.0 * (lo / 1000.0).log2()).ceil() as i32;
+                let last = (3.0 * (hi / 1000.0).log2()).floor() as i32;
+                let half = 2f32.powf(1.0 / 6.0);
+                (first..=last)
+                    .map(|k| {
+                        let centre = 1000.0 * 2f32.powf(k as f32 / 3.0);
+                        (centre / half, centre, centre * half)
+                    })
+                    .collect()
+            }
+        };
+
+        let bin_width = self.sample_rate / self.fft_size as f32;
+        let bins = self.amplitudes.len();
+        self.bands = edges
+            .into_iter()
+            .map(|(start, centre, end)| Band {
+                centre,
+                start: ((start / bin_width).ceil() as usize).min(bins),
+                end: ((end / bin_width).ceil() as usize).min(bins),
+                centre_bin: (centre / bin_width).min((bins - 1) as f32),
+            })
+            .collect();
+        let count = self.bands.len();
+        self.levels = vec![MIN_DB; count];
+        self.peaks = vec![MIN_DB; count];
+        self.peak_ages = vec![0.0; count];
+    }
+}
+
+// Loudest bin in the band, or the spectrum interpolated at its centre when
+// the band falls between two bins
+fn band_amplitude(band: &Band, amplitudes: &[f32]) -> f32 {
+    if band.end > band.start {
+        return amplitudes[band.start..band.end].iter().fold(0.0, |m, &a| a.max(m));
+    }
+    let i = band.centre_bin.floor() as usize;
+    let frac = band.centre_bin - i as f32;
+    let next = amplitudes.get(i + 1).copied().unwrap_or(amplitudes[i]);
+    amplitudes[i] + (next - amplitudes[i]) * frac
+}
+
+fn amplitude_db(amplitude: f32) -> f32 {
+    (20.0 * amplitude.max(1e-10).log10()).max(MIN_DB)
+}
+
+// One-pole coefficient for `dt` seconds of a `time_ms` time constant
+fn ballistics_coeff(dt: f32, time_ms: f32) -> f32 {
+    if time_ms <= 0.0 {
+        0.0
+    } else {
+        (-dt / (time_ms * 0.001)).exp()
+    }
+}
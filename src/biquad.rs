@@ -0,0 +1,287 @@
+use wasm_bindgen::prelude::*;
+
+use crate::smoothing::{SmoothedCoeffs, SmoothingMode};
+
+// Second-order responses from the Audio EQ Cookbook
+// https://webaudio.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterType {
+    LowPass,
+    HighPass,
+    // Band-pass with constant skirt gain (peak gain = Q)
+    BandPass,
+    // Band-pass with constant 0 dB peak gain
+    BandPassPeak,
+    Notch,
+    AllPass,
+    // Peaking EQ - uses gain_db
+    Peaking,
+    // Shelves - use gain_db
+    LowShelf,
+    HighShelf,
+}
+
+impl FilterType {
+    pub fn from_index(index: usize) -> Option<FilterType> {
+        [
+            FilterType::LowPass,
+            FilterType::HighPass,
+            FilterType::BandPass,
+            FilterType::BandPassPeak,
+            FilterType::Notch,
+            FilterType::AllPass,
+            FilterType::Peaking,
+            FilterType::LowShelf,
+            FilterType::HighShelf,
+        ]
+        .get(index)
+        .copied()
+    }
+}
+
+pub const BUTTERWORTH_Q: f32 = std::f32::consts::FRAC_1_SQRT_2;
+pub const Q_RANGE: (f32, f32) = (0.1, 24.0);
+pub const GAIN_DB_RANGE: (f32, f32) = (-48.0, 48.0);
+
+// Normalised coefficients [b0, b1, b2, a1, a2] (a0 divided out)
+pub fn coefficients(filter_type: FilterType, freq: f32, q: f32, gain_db: f32, sample_rate: f32) -> [f32; 5] {
+    let freq = freq.clamp(10.0, sample_rate * 0.45);
+    let q = q.clamp(Q_RANGE.0, Q_RANGE.1);
+    let a = 10f32.powf(gain_db / 40.0);
+    let w0 = 2.0 * std::f32::consts::PI * freq / sample_rate;
+    let cos_w0 = w0.cos();
+    let sin_w0 = w0.sin();
+    let alpha = sin_w0 / (2.0 * q);
+
+    let (b0, b1, b2, a0, a1, a2) = match filter_type {
+        FilterType::LowPass => (
+            (1.0 - cos_w0) / 2.0,
+            1.0 - cos_w0,
+            (1.0 - cos_w0) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos_w0,
+            1.0 - alpha,
+        ),
+        FilterType::HighPass => (
+            (1.0 + cos_w0) / 2.0,
+            -(1.0 + cos_w0),
+            (1.0 + cos_w0) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos_w0,
+            1.0 - alpha,
+        ),
+        FilterType::BandPass => (
+            sin_w0 / 2.0,
+            0.0,
+            -sin_w0 / 2.0,
+            1.0 + alpha,
+            -2.0 * cos_w0,
+            1.0 - alpha,
+        ),
+        FilterType::BandPassPeak => (alpha, 0.0, -alpha, 1.0 + alpha, -2.0 * cos_w0, 1.0 - alpha),
+        FilterType::Notch => (1.0, -2.0 * cos_w0, 1.0, 1.0 + alpha, -2.0 * cos_w0, 1.0 - alpha),
+        FilterType::AllPass => (
+            1.0 - alpha,
+            -2.0 * cos_w0,
+            1.0 + alpha,
+            1.0 + alpha,
+            -2.0 * cos_w0,
+            1.0 - alpha,
+        ),
+        FilterType::Peaking => (
+            1.0 + alpha * a,
+            -2.0 * cos_w0,
+            1.0 - alpha * a,
+            1.0 + alpha / a,
+            -2.0 * cos_w0,
+            1.0 - alpha / a,
+        ),
+        FilterType::LowShelf => {
+            let k = 2.0 * a.sqrt() * alpha;
+            (
+                a * ((a + 1.0) - (a - 1.0) * cos_w0 + k),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos_w0),
+                a * ((a + 1.0) - (a - 1.0) * cos_w0 - k),
+                (a + 1.0) + (a - 1.0) * cos_w0 + k,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos_w0),
+                (a + 1.0) + (a - 1.0) * cos_w0 - k,
+            )
+        }
+        FilterType::HighShelf => {
+            let k = 2.0 * a.sqrt() * alpha;
+            (
+                a * ((a + 1.0) + (a - 1.0) * cos_w0 + k),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos_w0),
+                a * ((a + 1.0) + (a - 1.0) * cos_w0 - k),
+                (a + 1.0) - (a - 1.0) * cos_w0 + k,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos_w0),
+                (a + 1.0) - (a - 1.0) * cos_w0 - k,
+            )
+        }
+    };
+
+    [b0 / a0, b1 / a0, b2 / a0, a1 / a0, a2 / a0]
+}
+
+// Q for a bandwidth in octaves (band-pass, notch, peaking)
+pub fn q_from_bandwidth(octaves: f32, freq: f32, sample_rate: f32) -> f32 {
+    let w0 = 2.0 * std::f32::consts::PI * freq.clamp(10.0, sample_rate * 0.45) / sample_rate;
+    let bw = octaves.max(0.01);
+    1.0 / (2.0 * (std::f32::consts::LN_2 / 2.0 * bw * w0 / w0.sin()).sinh())
+}
+
+// Q for a shelf slope S (1.0 = steepest without overshoot)
+pub fn q_from_shelf_slope(slope: f32, gain_db: f32) -> f32 {
+    let a = 10f32.powf(gain_db / 40.0);
+    let s = slope.clamp(0.01, 1.0);
+    let inv_q_sq = (a + 1.0 / a) * (1.0 / s - 1.0) + 2.0;
+    1.0 / inv_q_sq.sqrt()
+}
+
+// Direct-form-I biquad section with per-sample coefficient smoothing
+#[derive(Clone, Debug)]
+pub struct Biquad {
+    filter_type: FilterType,
+    frequency: f32,
+    q: f32,
+    gain_db: f32,
+    sample_rate: f32,
+    dirty: bool,
+    coeffs: SmoothedCoeffs,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl Biquad {
+    pub fn new(filter_type: FilterType, frequency: f32, sample_rate: f32) -> Biquad {
+        let q = BUTTERWORTH_Q;
+        let c = coefficients(filter_type, frequency, q, 0.0, sample_rate);
+        Biquad {
+            filter_type,
+            frequency,
+            q,
+            gain_db: 0.0,
+            sample_rate,
+            dirty: false,
+            coeffs: SmoothedCoeffs::new(c),
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        }
+    }
+
+    pub fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.coeffs.configure(mode, time_ms, self.sample_rate);
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            self.dirty = true;
+        }
+    }
+
+    pub fn set_type(&mut self, filter_type: FilterType) {
+        if filter_type != self.filter_type {
+            self.filter_type = filter_type;
+            self.dirty = true;
+        }
+    }
+
+    pub fn set_frequency(&mut self, frequency: f32) {
+        if frequency.is_finite() && frequency != self.frequency {
+            self.frequency = frequency;
+            self.dirty = true;
+        }
+    }
+
+    pub fn set_q(&mut self, q: f32) {
+        let q = q.clamp(Q_RANGE.0, Q_RANGE.1);
+        if q.is_finite() && q != self.q {
+            self.q = q;
+            self.dirty = true;
+        }
+    }
+
+    pub fn set_gain_db(&mut self, gain_db: f32) {
+        let gain_db = gain_db.clamp(GAIN_DB_RANGE.0, GAIN_DB_RANGE.1);
+        if gain_db.is_finite() && gain_db != self.gain_db {
+            self.gain_db = gain_db;
+            self.dirty = true;
+        }
+    }
+
+    // Bandwidth in octaves, converted to Q at the current frequency
+    pub fn set_bandwidth(&mut self, octaves: f32) {
+        self.set_q(q_from_bandwidth(octaves, self.frequency, self.sample_rate));
+    }
+
+    // Shelf slope S, converted to Q at the current gain
+    pub fn set_shelf_slope(&mut self, slope: f32) {
+        self.set_q(q_from_shelf_slope(slope, self.gain_db));
+    }
+
+    pub fn filter_type(&self) -> FilterType {
+        self.filter_type
+    }
+
+    pub fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    pub fn q(&self) -> f32 {
+        self.q
+    }
+
+    pub fn gain_db(&self) -> f32 {
+        self.gain_db
+    }
+
+    // Target coefficients for the current settings
+    pub fn coefficients(&self) -> [f32; 5] {
+        coefficients(self.filter_type, self.frequency, self.q, self.gain_db, self.sample_rate)
+    }
+
+    // Start gliding towards the current settings (no-op if nothing changed)
+    pub fn update(&mut self) {
+        if self.dirty {
+            self.dirty = false;
+            self.coeffs.set_target(self.coefficients());
+        }
+    }
+
+    // Jump to the current settings without gliding
+    pub fn snap(&mut self) {
+        self.dirty = false;
+        self.coeffs.snap(self.coefficients());
+    }
+
+    #[inline]
+    pub fn process_sample(&mut self, input: f32) -> f32 {
+        let c = self.coeffs.tick();
+        let out = c[0] * input + c[1] * self.x1 + c[2] * self.x2 - c[3] * self.y1 - c[4] * self.y2;
+        self.x2 = self.x1;
+        self.x1 = input;
+        self.y2 = self.y1;
+        self.y1 = out;
+        out
+    }
+
+    pub fn process(&mut self, buffer: &mut [f32]) {
+        self.update();
+        for sample in buffer.iter_mut() {
+            *sample = self.process_sample(*sample);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.x2 = 0.0;
+        self.y1 = 0.0;
+        self.y2 = 0.0;
+    }
+}
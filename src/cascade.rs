@@ -0,0 +1,195 @@
+use wasm_bindgen::prelude::*;
+
+use crate::biquad::{Biquad, FilterType, BUTTERWORTH_Q};
+use crate::smoothing::SmoothingMode;
+
+// Roll-off steepness of a low/high-pass filter
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterSlope {
+    Db12,
+    Db24,
+    Db36,
+    Db48,
+}
+
+// How the cascaded sections are tuned
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterAlignment {
+    // Maximally flat passband, -3 dB at the cutoff
+    Butterworth,
+    // Squared Butterworth, -6 dB at the cutoff (sums flat in crossovers)
+    LinkwitzRiley,
+}
+
+impl FilterAlignment {
+    pub fn from_index(index: usize) -> Option<FilterAlignment> {
+        [FilterAlignment::Butterworth, FilterAlignment::LinkwitzRiley]
+            .get(index)
+            .copied()
+    }
+}
+
+pub const MAX_SECTIONS: usize = 4;
+
+impl FilterSlope {
+    pub fn from_index(index: usize) -> Option<FilterSlope> {
+        [FilterSlope::Db12, FilterSlope::Db24, FilterSlope::Db36, FilterSlope::Db48]
+            .get(index)
+            .copied()
+    }
+
+    pub fn order(self) -> usize {
+        match self {
+            FilterSlope::Db12 => 2,
+            FilterSlope::Db24 => 4,
+            FilterSlope::Db36 => 6,
+            FilterSlope::Db48 => 8,
+        }
+    }
+}
+
+// Q values of the second-order sections of a Butterworth filter of `order`
+// An odd order leaves one real pole, reported separately
+fn butterworth_qs(order: usize) -> (Vec<f32>, bool) {
+    let n = order as f32;
+    let qs = (0..order / 2)
+        .map(|k| 1.0 / (2.0 * ((2 * k + 1) as f32 * std::f32::consts::PI / (2.0 * n)).sin()))
+        .collect();
+    (qs, order % 2 == 1)
+}
+
+// Per-section Q values for a slope/alignment, lowest Q first
+pub fn section_qs(slope: FilterSlope, alignment: FilterAlignment) -> Vec<f32> {
+    let mut qs = match alignment {
+        FilterAlignment::Butterworth => butterworth_qs(slope.order()).0,
+        FilterAlignment::LinkwitzRiley => {
+            // LR(2M) = BW(M) squared; two identical real poles make a Q=0.5 biquad
+            let (bw, real_pole) = butterworth_qs(slope.order() / 2);
+            let mut qs: Vec<f32> = bw.iter().chain(bw.iter()).copied().collect();
+            if real_pole {
+                qs.push(0.5);
+            }
+            qs
+        }
+    };
+    qs.sort_by(|a, b| a.total_cmp(b));
+    qs
+}
+
+// Filter built from up to four cascaded biquad sections of one type
+#[derive(Clone, Debug)]
+pub struct CascadedFilter {
+    sections: Vec<Biquad>,
+    active: usize,
+    slope: FilterSlope,
+    alignment: FilterAlignment,
+    resonance: f32,
+}
+
+impl CascadedFilter {
+    pub fn new(filter_type: FilterType, frequency: f32, sample_rate: f32) -> CascadedFilter {
+        let mut filter = CascadedFilter {
+            sections: (0..MAX_SECTIONS).map(|_| Biquad::new(filter_type, frequency, sample_rate)).collect(),
+            active: 1,
+            slope: FilterSlope::Db12,
+            alignment: FilterAlignment::Butterworth,
+            resonance: BUTTERWORTH_Q,
+        };
+        filter.retune();
+        filter.snap();
+        filter
+    }
+
+    pub fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        for s in self.sections.iter_mut() {
+            s.set_smoothing(mode, time_ms);
+        }
+    }
+
+    pub fn set_type(&mut self, filter_type: FilterType) {
+        for s in self.sections.iter_mut() {
+            s.set_type(filter_type);
+        }
+    }
+
+    pub fn set_frequency(&mut self, frequency: f32) {
+        for s in self.sections.iter_mut() {
+            s.set_frequency(frequency);
+        }
+    }
+
+    // Resonance of the whole filter - 0.707 gives the exact alignment,
+    // other values scale the sharpest section
+    pub fn set_q(&mut self, q: f32) {
+        if q.is_finite() && q > 0.0 && q != self.resonance {
+            self.resonance = q;
+            self.retune();
+        }
+    }
+
+    pub fn set_slope(&mut self, slope: FilterSlope) {
+        if slope != self.slope {
+            self.slope = slope;
+            self.retune();
+        }
+    }
+
+    pub fn set_alignment(&mut self, alignment: FilterAlignment) {
+        if alignment != self.alignment {
+            self.alignment = alignment;
+            self.retune();
+        }
+    }
+
+    pub fn slope(&self) -> FilterSlope {
+        self.slope
+    }
+
+    pub fn alignment(&self) -> FilterAlignment {
+        self.alignment
+    }
+
+    pub fn update(&mut self) {
+        for s in self.sections[..self.active].iter_mut() {
+            s.update();
+        }
+    }
+
+    pub fn snap(&mut self) {
+        for s in self.sections.iter_mut() {
+            s.snap();
+        }
+    }
+
+    #[inline]
+    pub fn process_sample(&mut self, input: f32) -> f32 {
+        let mut x = input;
+        for s in self.sections[..self.active].iter_mut() {
+            x = s.process_sample(x);
+        }
+        x
+    }
+
+    pub fn reset(&mut self) {
+        for s in self.sections.iter_mut() {
+            s.reset();
+        }
+    }
+
+    fn retune(&mut self) {
+        let qs = section_qs(self.slope, self.alignment);
+        let scale = self.resonance / BUTTERWORTH_Q;
+        let last = qs.len() - 1;
+        for (i, (section, &q)) in self.sections.iter_mut().zip(qs.iter()).enumerate() {
+            section.set_q(if i == last { q * scale } else { q });
+            // Newly switched-in sections start from silence
+            if i >= self.active {
+                section.reset();
+                section.snap();
+            }
+        }
+        self.active = qs.len();
+    }
+}
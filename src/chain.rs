@@ -0,0 +1,447 @@
+use crate::effects::Effect;
+use crate::smoothing::{SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+// Handle given to JS for an effect in a chain
+pub type EffectId = u32;
+
+pub const MAX_CHANNELS: usize = 8;
+
+struct Slot {
+    id: EffectId,
+    // One instance per channel (per channel pair for stereo effects)
+    // so every channel keeps its own state
+    instances: Vec<Box<dyn Effect>>,
+    stereo: bool,
+    // Linked: parameter changes go to every channel
+    // Independent: each channel is set on its own
+    linked: bool,
+    bypassed: bool,
+    // 1 = effect fully in, 0 = fully bypassed; ramps so bypass never clicks
+    wet: SmoothedValue,
+    // Input history per channel, `dry_len` samples each, so the dry signal
+    // can be delayed by the effect's latency when bypassed
+    dry: Vec<f32>,
+    dry_len: usize,
+    dry_pos: usize,
+}
+
+impl Slot {
+    fn set_channels(&mut self, channels: usize) {
+        let count = if self.stereo { channels.div_ceil(2) } else { channels };
+        self.instances.truncate(count);
+        while self.instances.len() < count {
+            let mut instance = self.instances[0].box_clone();
+            instance.reset();
+            self.instances.push(instance);
+        }
+        self.fit_dry(channels);
+    }
+
+    // Grow the dry history to the current latency
+    // Allocates, so only called from control calls, never from process
+    fn fit_dry(&mut self, channels: usize) {
+        let len = self.instances[0].latency().max(self.dry_len);
+        if len != self.dry_len || self.dry.len() != len * channels {
+            self.dry = vec![0.0; len * channels];
+            self.dry_len = len;
+            self.dry_pos = 0;
+        }
+    }
+
+    // Record `len` frames from `start` into the dry history
+    // With `replace` the buffer is swapped for the input from `latency`
+    // frames ago (capped at the history length)
+    fn delay_dry(&mut self, buffer: &mut [f32], channels: usize, frames: usize, start: usize, len: usize, replace: bool) {
+        let size = self.dry_len;
+        if size == 0 {
+            return;
+        }
+        let delay = self.instances[0].latency().min(size);
+        let channels = channels.min(self.dry.len() / size);
+        for c in 0..channels {
+            let history = &mut self.dry[c * size..(c + 1) * size];
+            let offset = c * frames + start;
+            let mut pos = self.dry_pos;
+            for x in buffer[offset..offset + len].iter_mut() {
+                let delayed = history[(pos + size - delay) % size];
+                history[pos] = *x;
+                if replace {
+                    *x = delayed;
+                }
+                pos = (pos + 1) % size;
+            }
+        }
+        self.dry_pos = (self.dry_pos + len) % size;
+    }
+
+    // Instance that processes `channel`
+    fn instance_index(&self, channel: usize) -> usize {
+        if self.stereo {
+            channel / 2
+        } else {
+            channel
+        }
+    }
+}
+
+// Ordered list of effects run in series
+// Effects can be added, removed, reordered and bypassed while running
+pub struct EffectChain {
+    sample_rate: f32,
+    max_block: usize,
+    channels: usize,
+    smoothing_mode: SmoothingMode,
+    smoothing_ms: f32,
+    slots: Vec<Slot>,
+    next_id: EffectId,
+    scratch: Vec<f32>,
+    // Bypass crossfade gains for the current block, shared by all channels
+    ramp: Vec<f32>,
+}
+
+impl EffectChain {
+    pub fn new(sample_rate: f32, max_block: usize) -> EffectChain {
+        let max_block = max_block.max(1);
+        EffectChain {
+            sample_rate,
+            max_block,
+            channels: 1,
+            smoothing_mode: SmoothingMode::Linear,
+            smoothing_ms: DEFAULT_SMOOTHING_MS,
+            slots: Vec::new(),
+            next_id: 1,
+            scratch: vec![0.0; max_block * MAX_CHANNELS],
+            ramp: vec![0.0; max_block],
+        }
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    pub fn max_block(&self) -> usize {
+        self.max_block
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    pub fn prepare(&mut self, sample_rate: f32, max_block: usize) {
+        self.sample_rate = sample_rate;
+        self.max_block = max_block.max(1);
+        self.scratch.resize(self.max_block * MAX_CHANNELS, 0.0);
+        self.ramp.resize(self.max_block, 0.0);
+        for slot in self.slots.iter_mut() {
+            for effect in slot.instances.iter_mut() {
+                effect.prepare(sample_rate, self.max_block);
+                effect.set_smoothing(self.smoothing_mode, self.smoothing_ms);
+            }
+            slot.wet.configure(SmoothingMode::Linear, self.smoothing_ms, sample_rate);
+            slot.fit_dry(self.channels);
+        }
+    }
+
+    // Number of channels processed (1..=MAX_CHANNELS)
+    // New channels copy the settings of channel 0 and start from silence
+    pub fn set_channels(&mut self, channels: usize) {
+        self.channels = channels.clamp(1, MAX_CHANNELS);
+        for slot in self.slots.iter_mut() {
+            slot.set_channels(self.channels);
+        }
+    }
+
+    pub fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.smoothing_mode = mode;
+        self.smoothing_ms = time_ms;
+        for slot in self.slots.iter_mut() {
+            for effect in slot.instances.iter_mut() {
+                effect.set_smoothing(mode, time_ms);
+            }
+            slot.wet.configure(SmoothingMode::Linear, time_ms, self.sample_rate);
+        }
+    }
+
+    // Append an effect and return its handle
+    pub fn push(&mut self, effect: Box<dyn Effect>) -> EffectId {
+        self.insert(self.slots.len(), effect)
+    }
+
+    // Insert at a position (clamped to the end of the chain)
+    pub fn insert(&mut self, index: usize, mut effect: Box<dyn Effect>) -> EffectId {
+        effect.prepare(self.sample_rate, self.max_block);
+        effect.set_smoothing(self.smoothing_mode, self.smoothing_ms);
+        let mut wet = SmoothedValue::new(1.0);
+        wet.configure(SmoothingMode::Linear, self.smoothing_ms, self.sample_rate);
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut slot = Slot {
+            id,
+            stereo: effect.is_stereo(),
+            instances: vec![effect],
+            linked: true,
+            bypassed: false,
+            wet,
+            dry: Vec::new(),
+            dry_len: 0,
+            dry_pos: 0,
+        };
+        slot.set_channels(self.channels);
+        let index = index.min(self.slots.len());
+        self.slots.insert(index, slot);
+        id
+    }
+
+    // Remove an effect, returning its channel-0 instance
+    pub fn remove(&mut self, id: EffectId) -> Option<Box<dyn Effect>> {
+        let index = self.index_of(id)?;
+        self.slots.remove(index).instances.into_iter().next()
+    }
+
+    // Move an effect to a new position (clamped to the end of the chain)
+    pub fn move_to(&mut self, id: EffectId, index: usize) -> bool {
+        match self.index_of(id) {
+            Some(from) => {
+                let slot = self.slots.remove(from);
+                let index = index.min(self.slots.len());
+                self.slots.insert(index, slot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_bypass(&mut self, id: EffectId, bypassed: bool) -> bool {
+        let channels = self.channels;
+        match self.slot_mut(id) {
+            Some(slot) => {
+                // Coming back from full bypass: start from clean state
+                if !bypassed && slot.bypassed && !slot.wet.is_smoothing() {
+                    for effect in slot.instances.iter_mut() {
+                        effect.reset();
+                    }
+                }
+                slot.fit_dry(channels);
+                slot.bypassed = bypassed;
+                slot.wet.set_target(if bypassed { 0.0 } else { 1.0 });
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_bypassed(&self, id: EffectId) -> Option<bool> {
+        self.slot(id).map(|s| s.bypassed)
+    }
+
+    // Linked effects take set_param on every channel; independent ones
+    // are set per channel. Re-linking keeps each channel's current
+    // settings until the next set_param.
+    pub fn set_linked(&mut self, id: EffectId, linked: bool) -> bool {
+        match self.slot_mut(id) {
+            Some(slot) => {
+                slot.linked = linked;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_linked(&self, id: EffectId) -> Option<bool> {
+        self.slot(id).map(|s| s.linked)
+    }
+
+    pub fn ids(&self) -> Vec<EffectId> {
+        self.slots.iter().map(|s| s.id).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn index_of(&self, id: EffectId) -> Option<usize> {
+        self.slots.iter().position(|s| s.id == id)
+    }
+
+    // Channel-0 instance of an effect
+    pub fn get(&self, id: EffectId) -> Option<&dyn Effect> {
+        self.get_channel(id, 0)
+    }
+
+    pub fn get_channel(&self, id: EffectId, channel: usize) -> Option<&dyn Effect> {
+        let slot = self.slot(id)?;
+        slot.instances.get(slot.instance_index(channel)).map(|e| e.as_ref())
+    }
+
+    pub fn get_mut(&mut self, id: EffectId) -> Option<&mut (dyn Effect + 'static)> {
+        self.get_channel_mut(id, 0)
+    }
+
+    pub fn get_channel_mut(&mut self, id: EffectId, channel: usize) -> Option<&mut (dyn Effect + 'static)> {
+        let slot = self.slot_mut(id)?;
+        let index = slot.instance_index(channel);
+        slot.instances.get_mut(index).map(|e| e.as_mut())
+    }
+
+    // Typed access to channel 0, e.g. chain.get_mut_as::<ParametricEq>(id)
+    pub fn get_mut_as<T: 'static>(&mut self, id: EffectId) -> Option<&mut T> {
+        self.get_mut(id)?.as_any_mut().downcast_mut::<T>()
+    }
+
+    pub fn get_channel_mut_as<T: 'static>(&mut self, id: EffectId, channel: usize) -> Option<&mut T> {
+        self.get_channel_mut(id, channel)?.as_any_mut().downcast_mut::<T>()
+    }
+
+    // Typed access to every channel's instance
+    // Returns false if the id is unknown or not a T
+    pub fn for_each_as<T: 'static>(&mut self, id: EffectId, mut f: impl FnMut(&mut T)) -> bool {
+        let Some(slot) = self.slot_mut(id) else {
+            return false;
+        };
+        let mut found = false;
+        for effect in slot.instances.iter_mut() {
+            if let Some(effect) = effect.as_any_mut().downcast_mut::<T>() {
+                f(effect);
+                found = true;
+            }
+        }
+        found
+    }
+
+    // Set a parameter on every channel
+    pub fn set_param(&mut self, id: EffectId, name: &str, value: f32) -> bool {
+        let Some(slot) = self.slot_mut(id) else {
+            return false;
+        };
+        let mut known = false;
+        for effect in slot.instances.iter_mut() {
+            known |= effect.set_param(name, value);
+        }
+        known
+    }
+
+    // Set a parameter on one channel of an independent (unlinked) effect
+    // For stereo effects this sets the channel's pair
+    pub fn set_channel_param(&mut self, id: EffectId, channel: usize, name: &str, value: f32) -> bool {
+        match self.slot_mut(id) {
+            Some(slot) if !slot.linked => {
+                let index = slot.instance_index(channel);
+                slot.instances.get_mut(index).is_some_and(|e| e.set_param(name, value))
+            }
+            _ => false,
+        }
+    }
+
+    pub fn get_param(&self, id: EffectId, name: &str) -> Option<f32> {
+        self.get(id)?.get_param(name)
+    }
+
+    pub fn get_channel_param(&self, id: EffectId, channel: usize, name: &str) -> Option<f32> {
+        self.get_channel(id, channel)?.get_param(name)
+    }
+
+    // Total latency of the chain
+    // Bypassed effects keep theirs, as their dry signal is delayed to match
+    pub fn latency(&self) -> usize {
+        self.slots.iter().map(|s| s.instances[0].latency()).sum()
+    }
+
+    // Process a mono buffer with the channel-0 instances
+    pub fn process(&mut self, buffer: &mut [f32]) {
+        self.process_planar(buffer, 1);
+    }
+
+    // Process planar audio: `channels` equal-length runs, one per channel
+    // Channels beyond the configured count pass through untouched
+    pub fn process_planar(&mut self, buffer: &mut [f32], channels: usize) {
+        if channels == 0 {
+            return;
+        }
+        let frames = buffer.len() / channels;
+        let mut start = 0;
+        while start < frames {
+            let len = (frames - start).min(self.max_block);
+            for slot in self.slots.iter_mut() {
+                if !slot.wet.is_smoothing() {
+                    // Keep the dry history fed so a later bypass lines up
+                    slot.delay_dry(buffer, channels, frames, start, len, slot.bypassed);
+                    if !slot.bypassed {
+                        Self::run_slot(slot, buffer, channels, frames, start, len);
+                    }
+                    continue;
+                }
+
+                // Bypass is fading - run the effect on a copy and crossfade
+                let ramp = &mut self.ramp[..len];
+                for r in ramp.iter_mut() {
+                    *r = slot.wet.tick();
+                }
+                let wet = &mut self.scratch[..channels * len];
+                for c in 0..channels {
+                    let offset = c * frames + start;
+                    wet[c * len..(c + 1) * len].copy_from_slice(&buffer[offset..offset + len]);
+                }
+                Self::run_slot(slot, wet, channels, len, 0, len);
+                // Dry delayed by the latency so the fade doesn't comb-filter
+                slot.delay_dry(buffer, channels, frames, start, len, true);
+                for c in 0..channels {
+                    let offset = c * frames + start;
+                    let chunk = &mut buffer[offset..offset + len];
+                    let processed = &wet[c * len..(c + 1) * len];
+                    for ((dry, &w), &amount) in chunk.iter_mut().zip(processed.iter()).zip(ramp.iter()) {
+                        *dry += (w - *dry) * amount;
+                    }
+                }
+            }
+            start += len;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for slot in self.slots.iter_mut() {
+            for effect in slot.instances.iter_mut() {
+                effect.reset();
+            }
+            let target = slot.wet.target();
+            slot.wet.snap(target);
+        }
+    }
+
+    // Run one slot over `len` frames from `start` of a planar buffer
+    // holding `channels` runs of `frames` samples
+    fn run_slot(slot: &mut Slot, buffer: &mut [f32], channels: usize, frames: usize, start: usize, len: usize) {
+        if !slot.stereo {
+            for (c, effect) in slot.instances.iter_mut().take(channels).enumerate() {
+                let offset = c * frames + start;
+                effect.process(&mut buffer[offset..offset + len]);
+            }
+            return;
+        }
+        for (pair, effect) in slot.instances.iter_mut().enumerate() {
+            let c = pair * 2;
+            if c >= channels {
+                break;
+            }
+            let offset = c * frames + start;
+            if c + 1 < channels {
+                let (left, right) = buffer.split_at_mut(offset + frames);
+                effect.process_stereo(&mut left[offset..offset + len], &mut right[..len]);
+            } else {
+                effect.process(&mut buffer[offset..offset + len]);
+            }
+        }
+    }
+
+    fn slot(&self, id: EffectId) -> Option<&Slot> {
+        self.slots.iter().find(|s| s.id == id)
+    }
+
+    fn slot_mut(&mut self, id: EffectId) -> Option<&mut Slot> {
+        self.slots.iter_mut().find(|s| s.id == id)
+    }
+}
@@ -0,0 +1,174 @@
+use wasm_bindgen::prelude::*;
+
+use crate::smoothing::{SmoothedValue, SmoothingMode};
+
+// How a delay line reads between two stored samples
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DelayInterpolation {
+    // Round to the nearest sample (the original behaviour)
+    None,
+    Linear,
+    // 4-point, 3rd-order Hermite
+    Cubic,
+    // First-order allpass - flat magnitude, best for fixed or slow delays
+    Allpass,
+}
+
+impl DelayInterpolation {
+    pub fn from_index(index: usize) -> Option<DelayInterpolation> {
+        [
+            DelayInterpolation::None,
+            DelayInterpolation::Linear,
+            DelayInterpolation::Cubic,
+            DelayInterpolation::Allpass,
+        ]
+        .get(index)
+        .copied()
+    }
+}
+
+pub const DEFAULT_GLIDE_MS: f32 = 50.0;
+
+// Circular delay buffer with fractional read positions
+// Changing the delay time glides the read head instead of jumping it
+#[derive(Clone, Debug)]
+pub struct DelayLine {
+    buffer: Vec<f32>,
+    write_pos: usize,
+    interpolation: DelayInterpolation,
+    delay: SmoothedValue,
+    allpass_y1: f32,
+}
+
+impl DelayLine {
+    pub fn new(max_delay_samples: usize) -> DelayLine {
+        // Two guard samples so cubic reads never wrap onto the write head
+        DelayLine {
+            buffer: vec![0.0; max_delay_samples.max(1) + 2],
+            write_pos: 0,
+            interpolation: DelayInterpolation::Linear,
+            delay: SmoothedValue::new(1.0),
+            allpass_y1: 0.0,
+        }
+    }
+
+    pub fn set_interpolation(&mut self, interpolation: DelayInterpolation) {
+        self.interpolation = interpolation;
+        self.allpass_y1 = 0.0;
+    }
+
+    pub fn interpolation(&self) -> DelayInterpolation {
+        self.interpolation
+    }
+
+    pub fn set_glide(&mut self, mode: SmoothingMode, time_ms: f32, sample_rate: f32) {
+        self.delay.configure(mode, time_ms, sample_rate);
+    }
+
+    // Longest delay that can be read, in samples
+    pub fn max_delay(&self) -> f32 {
+        (self.buffer.len() - 2) as f32
+    }
+
+    // New delay time in samples - the read head glides there
+    pub fn set_delay(&mut self, samples: f32) {
+        self.delay.set_target(self.clamp_delay(samples));
+    }
+
+    // Jump to a delay time without gliding
+    pub fn snap_delay(&mut self, samples: f32) {
+        self.delay.snap(self.clamp_delay(samples));
+    }
+
+    pub fn delay(&self) -> f32 {
+        self.delay.current()
+    }
+
+    pub fn target_delay(&self) -> f32 {
+        self.delay.target()
+    }
+
+    // Read at the (gliding) delay time - call once per sample before write()
+    pub fn read(&mut self) -> f32 {
+        let delay = self.delay.tick();
+        self.read_at(delay)
+    }
+
+    // Read at an explicit delay in samples, for modulated effects
+    pub fn read_at(&mut self, delay: f32) -> f32 {
+        let delay = self.clamp_delay(delay);
+        let whole = delay.floor();
+        let frac = delay - whole;
+        let i = whole as usize;
+
+        match self.interpolation {
+            DelayInterpolation::None => self.tap(delay.round() as usize),
+            DelayInterpolation::Linear => {
+                let a = self.tap(i);
+                let b = self.tap(i + 1);
+                a + (b - a) * frac
+            }
+            DelayInterpolation::Cubic => {
+                // Delay 0 has not been written yet - reuse the newest sample
+                let xm1 = self.tap(i.saturating_sub(1).max(1));
+                let x0 = self.tap(i);
+                let x1 = self.tap(i + 1);
+                let x2 = self.tap(i + 2);
+                let c1 = 0.5 * (x1 - xm1);
+                let c2 = xm1 - 2.5 * x0 + 2.0 * x1 - 0.5 * x2;
+                let c3 = 0.5 * (x2 - xm1) + 1.5 * (x0 - x1);
+                ((c3 * frac + c2) * frac + c1) * frac + x0
+            }
+            DelayInterpolation::Allpass => {
+                let eta = (1.0 - frac) / (1.0 + frac);
+                let y = eta * self.tap(i) + self.tap(i + 1) - eta * self.allpass_y1;
+                self.allpass_y1 = y;
+                y
+            }
+        }
+    }
+
+    // Store the next input sample and advance the write head
+    pub fn write(&mut self, x: f32) {
+        self.buffer[self.write_pos] = x;
+        self.write_pos = (self.write_pos + 1) % self.buffer.len();
+    }
+
+    pub fn reset(&mut self) {
+        for s in self.buffer.iter_mut() {
+            *s = 0.0;
+        }
+        self.write_pos = 0;
+        self.allpass_y1 = 0.0;
+        let target = self.delay.target();
+        self.delay.snap(target);
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    // Sample written `delay` samples ago (1 = most recent)
+    fn tap(&self, delay: usize) -> f32 {
+        let len = self.buffer.len();
+        let delay = delay.min(len - 1);
+        self.buffer[(self.write_pos + len - delay) % len]
+    }
+
+    fn clamp_delay(&self, samples: f32) -> f32 {
+        if samples.is_finite() {
+            samples.clamp(1.0, self.max_delay())
+        } else {
+            1.0
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::any::Any;
+use std::f32::consts::{FRAC_PI_4, SQRT_2};
+
+use super::tremolo::TREMOLO_SMOOTHING_RANGE;
+use super::{param_index, Effect};
+use crate::lfo::{Lfo, LfoShape};
+use crate::params::UNIT_RANGE;
+use crate::smoothing::{one_pole, SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+// Auto-pan
+//
+// Moves a stereo pair between the speakers with the LFO, using an
+// equal-power law that leaves the centre at unity. Corner smoothing works
+// as in Tremolo. A lone (mono) channel has nowhere to move and passes through.
+// Params: "rate" (Hz), "depth" (0..1, 1 = hard left to hard right),
+//         "shape" (LfoShape index), "smoothing" (ms)
+#[derive(Clone, Debug)]
+pub struct AutoPan {
+    sample_rate: f32,
+    depth: f32,
+    smoothing_ms: f32,
+    lfo: Lfo,
+    shaped: f32,
+    depth_s: SmoothedValue,
+    primed: bool,
+}
+
+impl AutoPan {
+    pub fn new(sample_rate: f32) -> AutoPan {
+        AutoPan {
+            sample_rate,
+            depth: 0.8,
+            smoothing_ms: 5.0,
+            lfo: Lfo::new(LfoShape::Sine, 0.5, sample_rate),
+            shaped: 0.0,
+            depth_s: SmoothedValue::new(0.0),
+            primed: false,
+        }
+    }
+
+    pub fn set_rate(&mut self, hz: f32) {
+        self.lfo.set_rate(hz);
+    }
+
+    pub fn set_depth(&mut self, depth: f32) {
+        if depth.is_finite() {
+            self.depth = depth.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.lfo.set_shape(shape);
+    }
+
+    pub fn set_smoothing_time(&mut self, time_ms: f32) {
+        if time_ms.is_finite() {
+            self.smoothing_ms = time_ms.clamp(TREMOLO_SMOOTHING_RANGE.0, TREMOLO_SMOOTHING_RANGE.1);
+        }
+    }
+}
+
+impl Effect for AutoPan {
+    fn name(&self) -> &'static str {
+        "auto_pan"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.sample_rate = sample_rate;
+        self.lfo.set_sample_rate(sample_rate);
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn process(&mut self, _buffer: &mut [f32]) {}
+
+    fn is_stereo(&self) -> bool {
+        true
+    }
+
+    fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        if self.primed {
+            self.depth_s.set_target(self.depth);
+        } else {
+            self.depth_s.snap(self.depth);
+            self.shaped = self.lfo.value_at(0.0);
+            self.primed = true;
+        }
+        let coeff = if self.smoothing_ms > 0.0 { one_pole(self.smoothing_ms, self.sample_rate) } else { 0.0 };
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            let lfo = self.lfo.tick();
+            self.shaped = lfo + (self.shaped - lfo) * coeff;
+            // Pan position -1..1 mapped onto a quarter circle
+            let angle = (self.depth_s.tick() * self.shaped + 1.0) * FRAC_PI_4;
+            let (gain_r, gain_l) = angle.sin_cos();
+            *l *= gain_l * SQRT_2;
+            *r *= gain_r * SQRT_2;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.lfo.reset();
+        self.primed = false;
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "rate" => self.set_rate(value),
+            "depth" => self.set_depth(value),
+            "shape" => match param_index(value).and_then(LfoShape::from_index) {
+                Some(shape) => self.set_shape(shape),
+                None => return false,
+            },
+            "smoothing" => self.set_smoothing_time(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "rate" => Some(self.lfo.rate()),
+            "depth" => Some(self.depth),
+            "shape" => Some(self.lfo.shape() as u32 as f32),
+            "smoothing" => Some(self.smoothing_ms),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.depth_s.configure(mode, time_ms, self.sample_rate);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
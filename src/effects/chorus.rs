@@ -0,0 +1,232 @@
+use std::any::Any;
+use std::f32::consts::{FRAC_PI_2, SQRT_2};
+
+use super::Effect;
+use crate::delay_line::{DelayInterpolation, DelayLine};
+use crate::lfo::{Lfo, LfoShape};
+use crate::params::UNIT_RANGE;
+use crate::smoothing::{SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+pub const CHORUS_VOICES_RANGE: (f32, f32) = (1.0, 8.0);
+// Centre delay of the voices, in ms
+pub const CHORUS_DELAY_RANGE: (f32, f32) = (5.0, 40.0);
+// Peak delay swing either side of the centre, in ms (limited to the centre delay)
+pub const CHORUS_DEPTH_RANGE: (f32, f32) = (0.0, 10.0);
+
+// Multi-voice chorus
+//
+// Each voice is a tap on one delay line, swept around the centre delay by
+// a shared sine LFO; the voices sit at even phase offsets so they never
+// bunch up. Stereo pairs feed the line with the mid signal and pan the
+// voices from left to right across `spread`.
+// Params: "voices" (1..8), "rate" (Hz), "depth" (ms), "delay" (ms),
+//         "spread" (0..1 stereo width of the voices), "mix" (0..1 equal-power)
+#[derive(Clone, Debug)]
+pub struct Chorus {
+    sample_rate: f32,
+    voices: usize,
+    depth_ms: f32,
+    delay_ms: f32,
+    spread: f32,
+    mix: f32,
+    lfo: Lfo,
+    line: DelayLine,
+    depth_s: SmoothedValue,
+    delay_s: SmoothedValue,
+    spread_s: SmoothedValue,
+    mix_s: SmoothedValue,
+    primed: bool,
+}
+
+impl Chorus {
+    pub fn new(sample_rate: f32) -> Chorus {
+        Chorus {
+            sample_rate,
+            voices: 3,
+            depth_ms: 3.0,
+            delay_ms: 15.0,
+            spread: 1.0,
+            mix: 0.5,
+            lfo: Lfo::new(LfoShape::Sine, 0.8, sample_rate),
+            line: Chorus::delay_line(sample_rate),
+            depth_s: SmoothedValue::new(0.0),
+            delay_s: SmoothedValue::new(0.0),
+            spread_s: SmoothedValue::new(0.0),
+            mix_s: SmoothedValue::new(0.0),
+            primed: false,
+        }
+    }
+
+    fn delay_line(sample_rate: f32) -> DelayLine {
+        let max_ms = CHORUS_DELAY_RANGE.1 + CHORUS_DEPTH_RANGE.1;
+        let mut line = DelayLine::new((max_ms * 0.001 * sample_rate).ceil() as usize + 2);
+        line.set_interpolation(DelayInterpolation::Cubic);
+        line
+    }
+
+    pub fn set_voices(&mut self, voices: f32) {
+        if voices.is_finite() {
+            self.voices = voices.round().clamp(CHORUS_VOICES_RANGE.0, CHORUS_VOICES_RANGE.1) as usize;
+        }
+    }
+
+    pub fn set_rate(&mut self, hz: f32) {
+        self.lfo.set_rate(hz);
+    }
+
+    pub fn set_depth(&mut self, ms: f32) {
+        if ms.is_finite() {
+            self.depth_ms = ms.clamp(CHORUS_DEPTH_RANGE.0, CHORUS_DEPTH_RANGE.1);
+        }
+    }
+
+    pub fn set_delay(&mut self, ms: f32) {
+        if ms.is_finite() {
+            self.delay_ms = ms.clamp(CHORUS_DELAY_RANGE.0, CHORUS_DELAY_RANGE.1);
+        }
+    }
+
+    // 0 = every voice in the centre, 1 = voices spread hard left to hard right
+    pub fn set_spread(&mut self, spread: f32) {
+        if spread.is_finite() {
+            self.spread = spread.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        if mix.is_finite() {
+            self.mix = mix.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    fn prime(&mut self) {
+        if self.primed {
+            self.depth_s.set_target(self.depth_ms);
+            self.delay_s.set_target(self.delay_ms);
+            self.spread_s.set_target(self.spread);
+            self.mix_s.set_target(self.mix);
+        } else {
+            self.depth_s.snap(self.depth_ms);
+            self.delay_s.snap(self.delay_ms);
+            self.spread_s.snap(self.spread);
+            self.mix_s.snap(self.mix);
+            self.primed = true;
+        }
+    }
+
+    // Read every voice for one sample, calling `out` with each voice's
+    // output and its pan position (-1..1 before spread)
+    #[inline]
+    fn read_voices(&mut self, mut out: impl FnMut(f32, f32)) {
+        let samples_per_ms = 0.001 * self.sample_rate;
+        let delay = self.delay_s.tick();
+        // The swing stops one sample short of zero delay, so shallow centre
+        // delays keep a smooth sweep instead of clipping at the bottom
+        let depth = self.depth_s.tick().min(delay - 1.0 / samples_per_ms);
+        let voices = self.voices;
+        for v in 0..voices {
+            let lfo = self.lfo.value_at(v as f32 / voices as f32);
+            let y = self.line.read_at((delay + depth * lfo) * samples_per_ms);
+            let position = if voices > 1 { 2.0 * v as f32 / (voices - 1) as f32 - 1.0 } else { 0.0 };
+            out(y, position);
+        }
+        self.lfo.advance();
+    }
+}
+
+impl Effect for Chorus {
+    fn name(&self) -> &'static str {
+        "chorus"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            self.line = Chorus::delay_line(sample_rate);
+            self.lfo.set_sample_rate(sample_rate);
+            self.primed = false;
+        }
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        self.prime();
+        let norm = 1.0 / (self.voices as f32).sqrt();
+        for sample in buffer.iter_mut() {
+            let x = *sample;
+            let mut wet = 0.0;
+            self.read_voices(|y, _| wet += y);
+            self.spread_s.tick();
+            self.line.write(x);
+            let (wet_gain, dry_gain) = (self.mix_s.tick() * FRAC_PI_2).sin_cos();
+            *sample = x * dry_gain + wet * norm * wet_gain;
+        }
+    }
+
+    fn is_stereo(&self) -> bool {
+        true
+    }
+
+    fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        self.prime();
+        let norm = 1.0 / (self.voices as f32).sqrt();
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            let spread = self.spread_s.tick();
+            let (mut wet_l, mut wet_r) = (0.0, 0.0);
+            self.read_voices(|y, position| {
+                // Equal-power pan
+                let pan = position * spread;
+                wet_l += y * (0.5 * (1.0 - pan)).sqrt();
+                wet_r += y * (0.5 * (1.0 + pan)).sqrt();
+            });
+            self.line.write(0.5 * (*l + *r));
+            let (wet_gain, dry_gain) = (self.mix_s.tick() * FRAC_PI_2).sin_cos();
+            // Panned voices lose 3 dB in the centre - give it back
+            let wet_gain = wet_gain * norm * SQRT_2;
+            *l = *l * dry_gain + wet_l * wet_gain;
+            *r = *r * dry_gain + wet_r * wet_gain;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.line.reset();
+        self.lfo.reset();
+        self.primed = false;
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "voices" => self.set_voices(value),
+            "rate" => self.set_rate(value),
+            "depth" => self.set_depth(value),
+            "delay" => self.set_delay(value),
+            "spread" => self.set_spread(value),
+            "mix" => self.set_mix(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "voices" => Some(self.voices as f32),
+            "rate" => Some(self.lfo.rate()),
+            "depth" => Some(self.depth_ms),
+            "delay" => Some(self.delay_ms),
+            "spread" => Some(self.spread),
+            "mix" => Some(self.mix),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.depth_s.configure(mode, time_ms, self.sample_rate);
+        self.delay_s.configure(mode, time_ms, self.sample_rate);
+        self.spread_s.configure(mode, time_ms, self.sample_rate);
+        self.mix_s.configure(mode, time_ms, self.sample_rate);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
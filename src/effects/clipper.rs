@@ -0,0 +1,63 @@
+use std::any::Any;
+
+use super::Effect;
+
+// Hard clip at +/- ceiling (the original end-of-chain safety clamp)
+// Params: "ceiling" (linear, 0.01..1)
+#[derive(Clone, Debug)]
+pub struct Clipper {
+    ceiling: f32,
+}
+
+impl Clipper {
+    pub fn new() -> Clipper {
+        Clipper { ceiling: 0.95 }
+    }
+
+    pub fn set_ceiling(&mut self, ceiling: f32) {
+        if ceiling.is_finite() {
+            self.ceiling = ceiling.clamp(0.01, 1.0);
+        }
+    }
+}
+
+impl Default for Clipper {
+    fn default() -> Self {
+        Clipper::new()
+    }
+}
+
+impl Effect for Clipper {
+    fn name(&self) -> &'static str {
+        "clipper"
+    }
+
+    fn prepare(&mut self, _sample_rate: f32, _max_block: usize) {}
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        for sample in buffer.iter_mut() {
+            *sample = sample.clamp(-self.ceiling, self.ceiling);
+        }
+    }
+
+    fn reset(&mut self) {}
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "ceiling" => self.set_ceiling(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "ceiling" => Some(self.ceiling),
+            _ => None,
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,255 @@
+use std::any::Any;
+
+use wasm_bindgen::prelude::*;
+
+use super::{param_index, Effect};
+use crate::smoothing::{db_to_gain, gain_to_db, one_pole, SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+pub const COMPRESSOR_THRESHOLD_RANGE: (f32, f32) = (-60.0, 0.0);
+pub const COMPRESSOR_RATIO_RANGE: (f32, f32) = (1.0, 20.0);
+pub const COMPRESSOR_KNEE_RANGE: (f32, f32) = (0.0, 24.0);
+pub const COMPRESSOR_ATTACK_RANGE: (f32, f32) = (0.1, 200.0);
+pub const COMPRESSOR_RELEASE_RANGE: (f32, f32) = (10.0, 2000.0);
+pub const COMPRESSOR_MAKEUP_RANGE: (f32, f32) = (0.0, 24.0);
+
+// Averaging time of the RMS detector
+const RMS_WINDOW_MS: f32 = 10.0;
+
+// How the compressor measures the input level
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DetectorMode {
+    // Instantaneous sample level - catches transients
+    Peak,
+    // Short-term power - follows loudness, smoother on speech
+    Rms,
+}
+
+impl DetectorMode {
+    pub fn from_index(index: usize) -> Option<DetectorMode> {
+        [DetectorMode::Peak, DetectorMode::Rms].get(index).copied()
+    }
+}
+
+// Feed-forward compressor
+//
+// The detector level (dB) goes through a soft-knee gain computer; the
+// resulting gain reduction is smoothed with separate attack and release
+// time constants, then makeup gain is added.
+// Stereo pairs are linked on the louder side so the image does not shift.
+// Params: "threshold" (dBFS), "ratio" (1..20), "knee" (dB width), "attack",
+//         "release" (ms), "makeup" (dB), "detector" (DetectorMode index);
+//         read-only "gain_reduction" (dB)
+#[derive(Clone, Debug)]
+pub struct Compressor {
+    sample_rate: f32,
+    threshold_db: f32,
+    ratio: f32,
+    knee_db: f32,
+    attack_ms: f32,
+    release_ms: f32,
+    makeup_db: f32,
+    detector: DetectorMode,
+    // Mean square for the RMS detector
+    power: f32,
+    // Smoothed gain reduction in dB (positive)
+    reduction: f32,
+    makeup_s: SmoothedValue,
+    primed: bool,
+}
+
+impl Compressor {
+    pub fn new(sample_rate: f32) -> Compressor {
+        Compressor {
+            sample_rate,
+            threshold_db: -18.0,
+            ratio: 4.0,
+            knee_db: 6.0,
+            attack_ms: 10.0,
+            release_ms: 100.0,
+            makeup_db: 0.0,
+            detector: DetectorMode::Peak,
+            power: 0.0,
+            reduction: 0.0,
+            makeup_s: SmoothedValue::new(1.0),
+            primed: false,
+        }
+    }
+
+    pub fn set_threshold(&mut self, db: f32) {
+        if db.is_finite() {
+            self.threshold_db = db.clamp(COMPRESSOR_THRESHOLD_RANGE.0, COMPRESSOR_THRESHOLD_RANGE.1);
+        }
+    }
+
+    // Input dB above the threshold per output dB (1 = no compression)
+    pub fn set_ratio(&mut self, ratio: f32) {
+        if ratio.is_finite() {
+            self.ratio = ratio.clamp(COMPRESSOR_RATIO_RANGE.0, COMPRESSOR_RATIO_RANGE.1);
+        }
+    }
+
+    // Width of the soft knee centred on the threshold, in dB (0 = hard knee)
+    pub fn set_knee(&mut self, db: f32) {
+        if db.is_finite() {
+            self.knee_db = db.clamp(COMPRESSOR_KNEE_RANGE.0, COMPRESSOR_KNEE_RANGE.1);
+        }
+    }
+
+    pub fn set_attack(&mut self, time_ms: f32) {
+        if time_ms.is_finite() {
+            self.attack_ms = time_ms.clamp(COMPRESSOR_ATTACK_RANGE.0, COMPRESSOR_ATTACK_RANGE.1);
+        }
+    }
+
+    pub fn set_release(&mut self, time_ms: f32) {
+        if time_ms.is_finite() {
+            self.release_ms = time_ms.clamp(COMPRESSOR_RELEASE_RANGE.0, COMPRESSOR_RELEASE_RANGE.1);
+        }
+    }
+
+    pub fn set_makeup(&mut self, db: f32) {
+        if db.is_finite() {
+            self.makeup_db = db.clamp(COMPRESSOR_MAKEUP_RANGE.0, COMPRESSOR_MAKEUP_RANGE.1);
+        }
+    }
+
+    pub fn set_detector(&mut self, detector: DetectorMode) {
+        self.detector = detector;
+    }
+
+    // Current gain reduction in dB (positive, before makeup)
+    pub fn gain_reduction(&self) -> f32 {
+        self.reduction
+    }
+
+    // Static curve: gain reduction in dB for an input level in dB
+    pub fn curve(&self, level_db: f32) -> f32 {
+        let over = level_db - self.threshold_db;
+        let slope = 1.0 - 1.0 / self.ratio;
+        let knee = self.knee_db;
+        if 2.0 * over <= -knee {
+            0.0
+        } else if 2.0 * over.abs() < knee {
+            // Quadratic blend across the knee
+            slope * (over + knee * 0.5).powi(2) / (2.0 * knee)
+        } else {
+            slope * over
+        }
+    }
+
+    fn coeffs(&mut self) -> Coeffs {
+        if self.primed {
+            self.makeup_s.set_target(db_to_gain(self.makeup_db));
+        } else {
+            self.makeup_s.snap(db_to_gain(self.makeup_db));
+            self.primed = true;
+        }
+        let sr = self.sample_rate;
+        Coeffs {
+            rms: one_pole(RMS_WINDOW_MS, sr),
+            attack: one_pole(self.attack_ms, sr),
+            release: one_pole(self.release_ms, sr),
+        }
+    }
+
+    // Advance the detector by one sample (|x| of the louder side) and
+    // return the linear gain to apply
+    #[inline]
+    fn tick(&mut self, level: f32, c: &Coeffs) -> f32 {
+        let level_db = match self.detector {
+            DetectorMode::Peak => gain_to_db(level),
+            DetectorMode::Rms => {
+                self.power = level * level + (self.power - level * level) * c.rms;
+                0.5 * gain_to_db(self.power)
+            }
+        };
+        let target = self.curve(level_db);
+        let coeff = if target > self.reduction { c.attack } else { c.release };
+        self.reduction = target + (self.reduction - target) * coeff;
+        db_to_gain(-self.reduction) * self.makeup_s.tick()
+    }
+}
+
+// Per-block constants for tick()
+struct Coeffs {
+    rms: f32,
+    attack: f32,
+    release: f32,
+}
+
+impl Effect for Compressor {
+    fn name(&self) -> &'static str {
+        "compressor"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.sample_rate = sample_rate;
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        let c = self.coeffs();
+        for sample in buffer.iter_mut() {
+            *sample *= self.tick(sample.abs(), &c);
+        }
+    }
+
+    fn is_stereo(&self) -> bool {
+        true
+    }
+
+    fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let c = self.coeffs();
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            let gain = self.tick(l.abs().max(r.abs()), &c);
+            *l *= gain;
+            *r *= gain;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.power = 0.0;
+        self.reduction = 0.0;
+        self.primed = false;
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "threshold" => self.set_threshold(value),
+            "ratio" => self.set_ratio(value),
+            "knee" => self.set_knee(value),
+            "attack" => self.set_attack(value),
+            "release" => self.set_release(value),
+            "makeup" => self.set_makeup(value),
+            "detector" => match param_index(value).and_then(DetectorMode::from_index) {
+                Some(detector) => self.set_detector(detector),
+                None => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "threshold" => Some(self.threshold_db),
+            "ratio" => Some(self.ratio),
+            "knee" => Some(self.knee_db),
+            "attack" => Some(self.attack_ms),
+            "release" => Some(self.release_ms),
+            "makeup" => Some(self.makeup_db),
+            "detector" => Some(self.detector as u32 as f32),
+            "gain_reduction" => Some(self.reduction),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.makeup_s.configure(mode, time_ms, self.sample_rate);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,283 @@
+use std::any::Any;
+
+use super::Effect;
+use crate::fft::{Complex, RealFft};
+use crate::params::{GAIN_RANGE, UNIT_RANGE};
+use crate::resample::resample;
+use crate::smoothing::{SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+pub const DEFAULT_PARTITION_SIZE: usize = 512;
+pub const PARTITION_SIZE_RANGE: (usize, usize) = (64, 4096);
+
+// Longest impulse response kept after resampling, in seconds
+pub const MAX_IR_SECONDS: f32 = 10.0;
+
+// Uniformly partitioned overlap-save convolution
+//
+// The impulse response is cut into partitions of B samples whose spectra
+// are kept; every B input samples one FFT of the last 2B inputs is pushed
+// into a frequency-domain delay line and multiplied against all partitions.
+// Works with any block size up to max_block and adds B samples of latency.
+// Params: "mix" (0..1 equal-power, default fully wet), "output" (linear gain),
+//         "partition_size" (samples, rounded to a power of two)
+#[derive(Clone, Debug)]
+pub struct Convolver {
+    sample_rate: f32,
+    partition: usize,
+    mix: f32,
+    output: f32,
+    // Impulse response at the processor rate, kept so it can be re-partitioned
+    ir: Vec<f32>,
+    fft: RealFft,
+    ir_spectra: Vec<Vec<Complex>>,
+    // Spectra of past input blocks, newest at `head`
+    history: Vec<Vec<Complex>>,
+    head: usize,
+    accumulator: Vec<Complex>,
+    // Last 2B input samples (time domain)
+    window: Vec<f32>,
+    // Input collected for the next block and output of the last one
+    input: Vec<f32>,
+    output_block: Vec<f32>,
+    time: Vec<f32>,
+    // Dry signal delayed to line up with the wet output
+    dry: Vec<f32>,
+    pos: usize,
+    mix_s: SmoothedValue,
+    output_s: SmoothedValue,
+    primed: bool,
+}
+
+impl Convolver {
+    pub fn new(sample_rate: f32) -> Convolver {
+        let mut convolver = Convolver {
+            sample_rate,
+            partition: DEFAULT_PARTITION_SIZE,
+            mix: 1.0,
+            output: 1.0,
+            ir: Vec::new(),
+            fft: RealFft::new(2),
+            ir_spectra: Vec::new(),
+            history: Vec::new(),
+            head: 0,
+            accumulator: Vec::new(),
+            window: Vec::new(),
+            input: Vec::new(),
+            output_block: Vec::new(),
+            time: Vec::new(),
+            dry: Vec::new(),
+            pos: 0,
+            mix_s: SmoothedValue::new(1.0),
+            output_s: SmoothedValue::new(1.0),
+            primed: false,
+        };
+        convolver.partition_ir();
+        convolver
+    }
+
+    // Load an impulse response recorded at `ir_sample_rate`
+    // It is resampled to the processor rate and cut to MAX_IR_SECONDS
+    pub fn load_impulse_response(&mut self, ir: &[f32], ir_sample_rate: f32) {
+        let mut ir = resample(ir, ir_sample_rate, self.sample_rate);
+        ir.truncate((MAX_IR_SECONDS * self.sample_rate) as usize);
+        if ir.iter().any(|x| !x.is_finite()) {
+            ir.clear();
+        }
+        self.ir = ir;
+        self.partition_ir();
+    }
+
+    pub fn clear_impulse_response(&mut self) {
+        self.ir.clear();
+        self.partition_ir();
+    }
+
+    // Length of the loaded impulse response in samples (processor rate)
+    pub fn impulse_response_len(&self) -> usize {
+        self.ir.len()
+    }
+
+    pub fn set_partition_size(&mut self, size: usize) {
+        let size = size
+            .clamp(PARTITION_SIZE_RANGE.0, PARTITION_SIZE_RANGE.1)
+            .next_power_of_two();
+        if size != self.partition {
+            self.partition = size;
+            self.partition_ir();
+        }
+    }
+
+    pub fn partition_size(&self) -> usize {
+        self.partition
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        if mix.is_finite() {
+            self.mix = mix.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    pub fn set_output(&mut self, gain: f32) {
+        if gain.is_finite() {
+            self.output = gain.clamp(GAIN_RANGE.0, GAIN_RANGE.1);
+        }
+    }
+
+    // Rebuild the partition spectra and clear the convolution state
+    fn partition_ir(&mut self) {
+        let b = self.partition;
+        self.fft = RealFft::new(2 * b);
+        let bins = self.fft.bins();
+
+        let mut padded = vec![0.0; 2 * b];
+        self.ir_spectra = self
+            .ir
+            .chunks(b)
+            .map(|chunk| {
+                padded[..chunk.len()].copy_from_slice(chunk);
+                padded[chunk.len()..].fill(0.0);
+                let mut spectrum = vec![Complex::ZERO; bins];
+                self.fft.forward(&padded, &mut spectrum);
+                spectrum
+            })
+            .collect();
+
+        let partitions = self.ir_spectra.len().max(1);
+        self.history = vec![vec![Complex::ZERO; bins]; partitions];
+        self.accumulator = vec![Complex::ZERO; bins];
+        self.window = vec![0.0; 2 * b];
+        self.input = vec![0.0; b];
+        self.output_block = vec![0.0; b];
+        self.time = vec![0.0; 2 * b];
+        self.dry = vec![0.0; b];
+        self.head = 0;
+        self.pos = 0;
+    }
+
+    // Convolve the collected input block
+    fn run_block(&mut self) {
+        let b = self.partition;
+        self.window.copy_within(b.., 0);
+        self.window[b..].copy_from_slice(&self.input);
+
+        let partitions = self.ir_spectra.len();
+        self.head = (self.head + 1) % partitions;
+        self.fft.forward(&self.window, &mut self.history[self.head]);
+
+        self.accumulator.fill(Complex::ZERO);
+        for (k, ir) in self.ir_spectra.iter().enumerate() {
+            let input = &self.history[(self.head + partitions - k) % partitions];
+            for ((acc, &x), &h) in self.accumulator.iter_mut().zip(input.iter()).zip(ir.iter()) {
+                *acc = *acc + x * h;
+            }
+        }
+
+        self.fft.inverse(&self.accumulator, &mut self.time);
+        // Overlap-save: only the second half is free of wrap-around
+        self.output_block.copy_from_slice(&self.time[b..]);
+    }
+}
+
+impl Effect for Convolver {
+    fn name(&self) -> &'static str {
+        "convolver"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        if sample_rate != self.sample_rate {
+            // The stored IR is at the old rate - bring it across
+            let ir = std::mem::take(&mut self.ir);
+            let old_rate = self.sample_rate;
+            self.sample_rate = sample_rate;
+            self.load_impulse_response(&ir, old_rate);
+        }
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        // Nothing loaded - pass through without latency
+        if self.ir_spectra.is_empty() {
+            return;
+        }
+        if self.primed {
+            self.mix_s.set_target(self.mix);
+            self.output_s.set_target(self.output);
+        } else {
+            self.mix_s.snap(self.mix);
+            self.output_s.snap(self.output);
+            self.primed = true;
+        }
+
+        for sample in buffer.iter_mut() {
+            let x = *sample;
+            let wet = self.output_block[self.pos];
+            let dry = self.dry[self.pos];
+            self.input[self.pos] = x;
+            self.dry[self.pos] = x;
+            self.pos += 1;
+            if self.pos == self.partition {
+                self.run_block();
+                self.pos = 0;
+            }
+
+            let mix = self.mix_s.tick();
+            let output = self.output_s.tick();
+            // Equal-power mix
+            let (wet_gain, dry_gain) = (mix * std::f32::consts::FRAC_PI_2).sin_cos();
+            *sample = dry * dry_gain + wet * wet_gain * output;
+        }
+    }
+
+    fn reset(&mut self) {
+        for spectrum in self.history.iter_mut() {
+            spectrum.fill(Complex::ZERO);
+        }
+        self.window.fill(0.0);
+        self.input.fill(0.0);
+        self.output_block.fill(0.0);
+        self.dry.fill(0.0);
+        self.head = 0;
+        self.pos = 0;
+        self.primed = false;
+    }
+
+    fn latency(&self) -> usize {
+        if self.ir_spectra.is_empty() {
+            0
+        } else {
+            self.partition
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "mix" => self.set_mix(value),
+            "output" => self.set_output(value),
+            "partition_size" if value.is_finite() && value > 0.0 => {
+                self.set_partition_size(value as usize)
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "mix" => Some(self.mix),
+            "output" => Some(self.output),
+            "partition_size" => Some(self.partition as f32),
+            "latency" => Some(self.latency() as f32),
+            "ir_length" => Some(self.ir.len() as f32),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.mix_s.configure(mode, time_ms, self.sample_rate);
+        self.output_s.configure(mode, time_ms, self.sample_rate);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
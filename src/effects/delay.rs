@@ -0,0 +1,182 @@
+use std::any::Any;
+
+use super::{param_bool, param_index, Effect};
+use crate::delay_line::{DelayInterpolation, DelayLine, DEFAULT_GLIDE_MS};
+use crate::params::{DELAY_TIME_RANGE, UNIT_RANGE};
+use crate::smoothing::{SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+// Feedback echo on a one-second fractional delay line
+// Params: "time" (seconds), "feedback" (0..1 loop gain), "mix" (0..1 equal-power),
+//         "safe_mode" (0/1), "glide" (ms), "interpolation" (DelayInterpolation index)
+#[derive(Clone, Debug)]
+pub struct Delay {
+    sample_rate: f32,
+    time: f32,
+    feedback: f32,
+    mix: f32,
+    safe_mode: bool,
+    glide_ms: f32,
+    line: DelayLine,
+    feedback_s: SmoothedValue,
+    mix_s: SmoothedValue,
+    primed: bool,
+}
+
+impl Delay {
+    pub fn new(sample_rate: f32) -> Delay {
+        let mut delay = Delay {
+            sample_rate,
+            time: 0.0,
+            feedback: 0.0,
+            mix: 0.0,
+            safe_mode: true,
+            glide_ms: DEFAULT_GLIDE_MS,
+            line: DelayLine::new(sample_rate as usize),
+            feedback_s: SmoothedValue::new(0.0),
+            mix_s: SmoothedValue::new(0.0),
+            primed: false,
+        };
+        delay.set_glide(DEFAULT_GLIDE_MS);
+        delay
+    }
+
+    pub fn set_time(&mut self, seconds: f32) {
+        if seconds.is_finite() {
+            self.time = seconds.clamp(DELAY_TIME_RANGE.0, DELAY_TIME_RANGE.1);
+        }
+    }
+
+    pub fn set_feedback(&mut self, feedback: f32) {
+        if feedback.is_finite() {
+            self.feedback = feedback.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        if mix.is_finite() {
+            self.mix = mix.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    pub fn set_safe_mode(&mut self, safe_mode: bool) {
+        self.safe_mode = safe_mode;
+    }
+
+    // How long the read head takes to reach a new delay time (milliseconds)
+    pub fn set_glide(&mut self, time_ms: f32) {
+        self.glide_ms = if time_ms.is_finite() { time_ms.max(0.0) } else { DEFAULT_GLIDE_MS };
+        self.line.set_glide(SmoothingMode::Linear, self.glide_ms, self.sample_rate);
+    }
+
+    pub fn set_interpolation(&mut self, interpolation: DelayInterpolation) {
+        self.line.set_interpolation(interpolation);
+    }
+
+    // Delay buffer length in samples (for memory monitoring)
+    pub fn buffer_len(&self) -> usize {
+        self.line.len()
+    }
+
+    pub fn buffer_capacity(&self) -> usize {
+        self.line.capacity()
+    }
+}
+
+impl Effect for Delay {
+    fn name(&self) -> &'static str {
+        "delay"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            let interpolation = self.line.interpolation();
+            self.line = DelayLine::new(sample_rate as usize);
+            self.line.set_interpolation(interpolation);
+            self.set_glide(self.glide_ms);
+            self.primed = false;
+        }
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        let delay_samples = self.time * self.sample_rate;
+        // Fade the echo out rather than cutting it when delay time goes to zero
+        let mix = if self.time > 0.001 { self.mix } else { 0.0 };
+        if self.primed {
+            self.line.set_delay(delay_samples);
+            self.feedback_s.set_target(self.feedback);
+            self.mix_s.set_target(mix);
+        } else {
+            self.line.snap_delay(delay_samples);
+            self.feedback_s.snap(self.feedback);
+            self.mix_s.snap(mix);
+            self.primed = true;
+        }
+
+        for sample in buffer.iter_mut() {
+            let feedback = self.feedback_s.tick();
+            let mix = self.mix_s.tick();
+            let x = *sample;
+
+            // The read head always moves so time changes glide
+            let delayed = self.line.read();
+            if mix > 0.001 {
+                // Feedback is exactly what the UI asked for; safe mode
+                // rounds off the loop instead of capping it
+                let recirculated = delayed * feedback;
+                let recirculated = if self.safe_mode { recirculated.tanh() } else { recirculated };
+                self.line.write(x + recirculated);
+
+                // Equal-power mix
+                let (wet, dry) = (mix * std::f32::consts::FRAC_PI_2).sin_cos();
+                *sample = x * dry + delayed * wet;
+            } else {
+                // No delay - write silence
+                self.line.write(0.0);
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.line.reset();
+        self.primed = false;
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "time" => self.set_time(value),
+            "feedback" => self.set_feedback(value),
+            "mix" => self.set_mix(value),
+            "safe_mode" => self.set_safe_mode(param_bool(value)),
+            "glide" => self.set_glide(value),
+            "interpolation" => match param_index(value).and_then(DelayInterpolation::from_index) {
+                Some(interpolation) => self.set_interpolation(interpolation),
+                None => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "time" => Some(self.time),
+            "feedback" => Some(self.feedback),
+            "mix" => Some(self.mix),
+            "safe_mode" => Some(if self.safe_mode { 1.0 } else { 0.0 }),
+            "glide" => Some(self.glide_ms),
+            "interpolation" => Some(self.line.interpolation() as u32 as f32),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.feedback_s.configure(mode, time_ms, self.sample_rate);
+        self.mix_s.configure(mode, time_ms, self.sample_rate);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,117 @@
+use std::any::Any;
+
+use super::waveshaper::{normalisation, shape, WaveshaperCurve};
+use super::{param_index, Effect};
+use crate::params::UNIT_RANGE;
+use crate::smoothing::{SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+// Normalised saturation - tanh unless another curve is chosen
+// (see Waveshaper for tables, tone filters and mix)
+// Params: "amount" (0..1, drive = 1 + amount * 8),
+//         "curve" (WaveshaperCurve index, Table is not accepted)
+#[derive(Clone, Debug)]
+pub struct Distortion {
+    sample_rate: f32,
+    amount: f32,
+    curve: WaveshaperCurve,
+    smoothed: SmoothedValue,
+    primed: bool,
+}
+
+impl Distortion {
+    pub fn new() -> Distortion {
+        Distortion {
+            sample_rate: 48000.0,
+            amount: 0.0,
+            curve: WaveshaperCurve::SoftClip,
+            smoothed: SmoothedValue::new(0.0),
+            primed: false,
+        }
+    }
+
+    pub fn set_amount(&mut self, amount: f32) {
+        if amount.is_finite() {
+            self.amount = amount.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    pub fn amount(&self) -> f32 {
+        self.amount
+    }
+
+    // Any curve except Table
+    pub fn set_curve(&mut self, curve: WaveshaperCurve) {
+        if curve != WaveshaperCurve::Table {
+            self.curve = curve;
+        }
+    }
+
+    pub fn curve(&self) -> WaveshaperCurve {
+        self.curve
+    }
+}
+
+impl Default for Distortion {
+    fn default() -> Self {
+        Distortion::new()
+    }
+}
+
+impl Effect for Distortion {
+    fn name(&self) -> &'static str {
+        "distortion"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.sample_rate = sample_rate;
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        if self.primed {
+            self.smoothed.set_target(self.amount);
+        } else {
+            self.smoothed.snap(self.amount);
+            self.primed = true;
+        }
+        for sample in buffer.iter_mut() {
+            let amount = self.smoothed.tick();
+            if amount > 0.01 {
+                let drive = 1.0 + amount * 8.0;
+                *sample = shape(self.curve, *sample * drive) * normalisation(self.curve, drive);
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.primed = false;
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "amount" => self.set_amount(value),
+            "curve" => match param_index(value).and_then(WaveshaperCurve::from_index) {
+                Some(curve) if curve != WaveshaperCurve::Table => self.set_curve(curve),
+                _ => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "amount" => Some(self.amount),
+            "curve" => Some(self.curve as u32 as f32),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.smoothed.configure(mode, time_ms, self.sample_rate);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
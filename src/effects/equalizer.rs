@@ -0,0 +1,292 @@
+use std::any::Any;
+
+use wasm_bindgen::prelude::*;
+
+use super::{param_bool, param_index, Effect};
+use crate::biquad::{Biquad, FilterType, BUTTERWORTH_Q};
+use crate::smoothing::{SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+pub const MAX_EQ_BANDS: usize = 32;
+
+#[derive(Clone, Debug)]
+struct EqBand {
+    filter: Biquad,
+    enabled: bool,
+}
+
+// Parametric equalizer - a list of biquad bands run in series
+// Band indices are stable until a band is removed
+// Params: "type_N", "frequency_N", "gain_N", "q_N", "enabled_N" for band N
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct ParametricEq {
+    sample_rate: f32,
+    smoothing_mode: SmoothingMode,
+    smoothing_ms: f32,
+    bands: Vec<EqBand>,
+}
+
+#[wasm_bindgen]
+impl ParametricEq {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32) -> ParametricEq {
+        ParametricEq {
+            sample_rate,
+            smoothing_mode: SmoothingMode::Linear,
+            smoothing_ms: DEFAULT_SMOOTHING_MS,
+            bands: Vec::new(),
+        }
+    }
+
+    // Add a band and return its index (None when MAX_EQ_BANDS is reached
+    // or the frequency is not finite)
+    pub fn add_band(&mut self, filter_type: FilterType, frequency: f32, gain_db: f32, q: f32) -> Option<usize> {
+        if self.bands.len() >= MAX_EQ_BANDS || !frequency.is_finite() {
+            return None;
+        }
+        let mut filter = Biquad::new(filter_type, frequency, self.sample_rate);
+        filter.set_smoothing(self.smoothing_mode, self.smoothing_ms);
+        filter.set_gain_db(gain_db);
+        filter.set_q(if q > 0.0 { q } else { BUTTERWORTH_Q });
+        filter.snap();
+        self.bands.push(EqBand { filter, enabled: true });
+        Some(self.bands.len() - 1)
+    }
+
+    pub fn remove_band(&mut self, index: usize) -> bool {
+        if index < self.bands.len() {
+            self.bands.remove(index);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.bands.clear();
+    }
+
+    pub fn band_count(&self) -> usize {
+        self.bands.len()
+    }
+
+    // Change every setting of a band at once (returns false for a bad index)
+    pub fn set_band(&mut self, index: usize, filter_type: FilterType, frequency: f32, gain_db: f32, q: f32) -> bool {
+        match self.bands.get_mut(index) {
+            Some(band) => {
+                band.filter.set_type(filter_type);
+                band.filter.set_frequency(frequency);
+                band.filter.set_gain_db(gain_db);
+                band.filter.set_q(q);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_band_type(&mut self, index: usize, filter_type: FilterType) -> bool {
+        self.with_band(index, |f| f.set_type(filter_type))
+    }
+
+    pub fn set_band_frequency(&mut self, index: usize, frequency: f32) -> bool {
+        self.with_band(index, |f| f.set_frequency(frequency))
+    }
+
+    pub fn set_band_gain(&mut self, index: usize, gain_db: f32) -> bool {
+        self.with_band(index, |f| f.set_gain_db(gain_db))
+    }
+
+    pub fn set_band_q(&mut self, index: usize, q: f32) -> bool {
+        self.with_band(index, |f| f.set_q(q))
+    }
+
+    // Disabled bands are skipped; their filter state is cleared so
+    // re-enabling starts from silence instead of stale history
+    pub fn set_band_enabled(&mut self, index: usize, enabled: bool) -> bool {
+        match self.bands.get_mut(index) {
+            Some(band) => {
+                if enabled && !band.enabled {
+                    band.filter.reset();
+                }
+                band.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn band_type(&self, index: usize) -> Option<FilterType> {
+        self.bands.get(index).map(|b| b.filter.filter_type())
+    }
+
+    pub fn band_frequency(&self, index: usize) -> Option<f32> {
+        self.bands.get(index).map(|b| b.filter.frequency())
+    }
+
+    pub fn band_gain(&self, index: usize) -> Option<f32> {
+        self.bands.get(index).map(|b| b.filter.gain_db())
+    }
+
+    pub fn band_q(&self, index: usize) -> Option<f32> {
+        self.bands.get(index).map(|b| b.filter.q())
+    }
+
+    pub fn band_enabled(&self, index: usize) -> Option<bool> {
+        self.bands.get(index).map(|b| b.enabled)
+    }
+
+    pub fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.smoothing_mode = mode;
+        self.smoothing_ms = time_ms;
+        for band in self.bands.iter_mut() {
+            band.filter.set_smoothing(mode, time_ms);
+        }
+    }
+
+    // Combined magnitude response in dB at each frequency (for drawing the EQ curve)
+    pub fn response_db(&self, frequencies: &[f32]) -> Vec<f32> {
+        frequencies
+            .iter()
+            .map(|&f| {
+                let w = 2.0 * std::f32::consts::PI * f / self.sample_rate;
+                self.bands
+                    .iter()
+                    .filter(|b| b.enabled)
+                    .map(|b| magnitude_db(&b.filter.coefficients(), w))
+                    .sum()
+            })
+            .collect()
+    }
+
+    pub fn process(&mut self, buffer: &mut [f32]) {
+        self.update();
+        for sample in buffer.iter_mut() {
+            *sample = self.process_sample(*sample);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for band in self.bands.iter_mut() {
+            band.filter.reset();
+        }
+    }
+}
+
+impl ParametricEq {
+    // Push pending band changes into the coefficient smoothers
+    pub fn update(&mut self) {
+        for band in self.bands.iter_mut() {
+            band.filter.update();
+        }
+    }
+
+    pub fn snap(&mut self) {
+        for band in self.bands.iter_mut() {
+            band.filter.snap();
+        }
+    }
+
+    #[inline]
+    pub fn process_sample(&mut self, input: f32) -> f32 {
+        let mut x = input;
+        for band in self.bands.iter_mut().filter(|b| b.enabled) {
+            x = band.filter.process_sample(x);
+        }
+        x
+    }
+
+    fn with_band(&mut self, index: usize, f: impl FnOnce(&mut Biquad)) -> bool {
+        match self.bands.get_mut(index) {
+            Some(band) => {
+                f(&mut band.filter);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Effect for ParametricEq {
+    fn name(&self) -> &'static str {
+        "equalizer"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            for band in self.bands.iter_mut() {
+                band.filter.set_sample_rate(sample_rate);
+                band.filter.set_smoothing(self.smoothing_mode, self.smoothing_ms);
+            }
+            self.snap();
+        }
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        ParametricEq::process(self, buffer);
+    }
+
+    fn reset(&mut self) {
+        ParametricEq::reset(self);
+        self.snap();
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        let Some((param, index)) = split_band_param(name) else {
+            return false;
+        };
+        match param {
+            "type" => match param_index(value).and_then(FilterType::from_index) {
+                Some(filter_type) => self.set_band_type(index, filter_type),
+                None => false,
+            },
+            "frequency" => self.set_band_frequency(index, value),
+            "gain" => self.set_band_gain(index, value),
+            "q" => self.set_band_q(index, value),
+            "enabled" => self.set_band_enabled(index, param_bool(value)),
+            _ => false,
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        if name == "bands" {
+            return Some(self.bands.len() as f32);
+        }
+        let (param, index) = split_band_param(name)?;
+        match param {
+            "type" => self.band_type(index).map(|t| t as u32 as f32),
+            "frequency" => self.band_frequency(index),
+            "gain" => self.band_gain(index),
+            "q" => self.band_q(index),
+            "enabled" => self.band_enabled(index).map(|e| if e { 1.0 } else { 0.0 }),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        ParametricEq::set_smoothing(self, mode, time_ms);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// "gain_3" -> ("gain", 3)
+fn split_band_param(name: &str) -> Option<(&str, usize)> {
+    let (param, index) = name.rsplit_once('_')?;
+    Some((param, index.parse().ok()?))
+}
+
+// |H(e^jw)| in dB for normalised coefficients [b0, b1, b2, a1, a2]
+pub fn magnitude_db(c: &[f32; 5], w: f32) -> f32 {
+    let (s1, c1) = w.sin_cos();
+    let (s2, c2) = (2.0 * w).sin_cos();
+    let num_re = c[0] + c[1] * c1 + c[2] * c2;
+    let num_im = -(c[1] * s1 + c[2] * s2);
+    let den_re = 1.0 + c[3] * c1 + c[4] * c2;
+    let den_im = -(c[3] * s1 + c[4] * s2);
+    let num = num_re * num_re + num_im * num_im;
+    let den = den_re * den_re + den_im * den_im;
+    10.0 * (num.max(1e-20) / den.max(1e-20)).log10()
+}
@@ -0,0 +1,294 @@
+use std::any::Any;
+
+use super::{param_index, Effect};
+use crate::biquad::{FilterType, BUTTERWORTH_Q};
+use crate::cascade::{CascadedFilter, FilterAlignment, FilterSlope};
+use crate::params::UNIT_RANGE;
+use crate::smoothing::{one_pole, SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+use crate::svf::{FilterEngine, SvfCascade};
+
+// Cutoff sweep at full-scale envelope, in octaves (negative sweeps down)
+pub const FILTER_ENVELOPE_RANGE: (f32, f32) = (-4.0, 4.0);
+pub const FILTER_ATTACK_RANGE: (f32, f32) = (0.1, 100.0);
+pub const FILTER_RELEASE_RANGE: (f32, f32) = (5.0, 2000.0);
+
+// Low-, high-, band-pass or notch filter with selectable slope and engine
+// Switching engines crossfades between them. The state-variable engine
+// also takes a 0..1 resonance (1 self-oscillates) and an envelope follower
+// that sweeps its cutoff per sample for auto-wah and envelope filter sounds;
+// the biquads ignore both, as they misbehave under per-sample changes.
+// Params: "type" (FilterType index: LowPass, HighPass, BandPass or Notch),
+//         "cutoff" (Hz), "q", "slope" (FilterSlope index),
+//         "alignment" (FilterAlignment index), "engine" (FilterEngine index),
+//         "resonance" (0..1, SVF only, 0 = use q), "envelope" (octaves, SVF
+//         only, 0 = off), "attack" (ms), "release" (ms)
+#[derive(Clone, Debug)]
+pub struct Filter {
+    filter_type: FilterType,
+    sample_rate: f32,
+    cutoff: f32,
+    q: f32,
+    slope: FilterSlope,
+    alignment: FilterAlignment,
+    engine: FilterEngine,
+    resonance: f32,
+    envelope: f32,
+    attack_ms: f32,
+    release_ms: f32,
+    biquad: CascadedFilter,
+    svf: SvfCascade,
+    engine_mix: SmoothedValue,
+    envelope_s: SmoothedValue,
+    // Envelope follower level
+    level: f32,
+    primed: bool,
+}
+
+impl Filter {
+    pub fn new(filter_type: FilterType, cutoff: f32, sample_rate: f32) -> Filter {
+        Filter {
+            filter_type,
+            sample_rate,
+            cutoff,
+            q: BUTTERWORTH_Q,
+            slope: FilterSlope::Db12,
+            alignment: FilterAlignment::Butterworth,
+            engine: FilterEngine::Biquad,
+            resonance: 0.0,
+            envelope: 0.0,
+            attack_ms: 5.0,
+            release_ms: 150.0,
+            biquad: CascadedFilter::new(filter_type, cutoff, sample_rate),
+            svf: SvfCascade::new(filter_type, cutoff, sample_rate),
+            engine_mix: SmoothedValue::new(0.0),
+            envelope_s: SmoothedValue::new(0.0),
+            level: 0.0,
+            primed: false,
+        }
+    }
+
+    // Fully open by default, like the processor's clean settings
+    pub fn low_pass(sample_rate: f32) -> Filter {
+        Filter::new(FilterType::LowPass, 20000.0, sample_rate)
+    }
+
+    pub fn high_pass(sample_rate: f32) -> Filter {
+        Filter::new(FilterType::HighPass, 20.0, sample_rate)
+    }
+
+    // Responses both engines share; others are ignored
+    pub fn supports(filter_type: FilterType) -> bool {
+        matches!(
+            filter_type,
+            FilterType::LowPass | FilterType::HighPass | FilterType::BandPass | FilterType::Notch
+        )
+    }
+
+    pub fn set_filter_type(&mut self, filter_type: FilterType) {
+        if Filter::supports(filter_type) {
+            self.filter_type = filter_type;
+        }
+    }
+
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        if cutoff.is_finite() {
+            self.cutoff = cutoff;
+        }
+    }
+
+    pub fn set_q(&mut self, q: f32) {
+        if q.is_finite() && q > 0.0 {
+            self.q = q;
+        }
+    }
+
+    pub fn set_slope(&mut self, slope: FilterSlope) {
+        self.slope = slope;
+    }
+
+    pub fn set_alignment(&mut self, alignment: FilterAlignment) {
+        self.alignment = alignment;
+    }
+
+    pub fn set_engine(&mut self, engine: FilterEngine) {
+        self.engine = engine;
+    }
+
+    pub fn set_resonance(&mut self, resonance: f32) {
+        if resonance.is_finite() {
+            self.resonance = resonance.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    pub fn set_envelope(&mut self, octaves: f32) {
+        if octaves.is_finite() {
+            self.envelope = octaves.clamp(FILTER_ENVELOPE_RANGE.0, FILTER_ENVELOPE_RANGE.1);
+        }
+    }
+
+    pub fn set_attack(&mut self, time_ms: f32) {
+        if time_ms.is_finite() {
+            self.attack_ms = time_ms.clamp(FILTER_ATTACK_RANGE.0, FILTER_ATTACK_RANGE.1);
+        }
+    }
+
+    pub fn set_release(&mut self, time_ms: f32) {
+        if time_ms.is_finite() {
+            self.release_ms = time_ms.clamp(FILTER_RELEASE_RANGE.0, FILTER_RELEASE_RANGE.1);
+        }
+    }
+
+    pub fn cutoff(&self) -> f32 {
+        self.cutoff
+    }
+
+    // Push settings into both engines; the incoming engine starts clean
+    fn update(&mut self) {
+        self.biquad.set_type(self.filter_type);
+        self.svf.set_type(self.filter_type);
+        self.svf.set_resonance(self.resonance);
+        self.biquad.set_frequency(self.cutoff);
+        self.biquad.set_q(self.q);
+        self.biquad.set_slope(self.slope);
+        self.biquad.set_alignment(self.alignment);
+        self.svf.set_q(self.q);
+        self.svf.set_slope(self.slope);
+        self.svf.set_alignment(self.alignment);
+        let engine = match self.engine {
+            FilterEngine::Biquad => 0.0,
+            FilterEngine::StateVariable => 1.0,
+        };
+
+        if !self.primed {
+            self.engine_mix.snap(engine);
+            self.envelope_s.snap(self.envelope);
+            self.svf.snap_cutoff(self.cutoff);
+            self.biquad.snap();
+            self.primed = true;
+            return;
+        }
+
+        if engine != self.engine_mix.target() && !self.engine_mix.is_smoothing() {
+            if engine > 0.0 {
+                self.svf.reset();
+                self.svf.snap_cutoff(self.cutoff);
+            } else {
+                self.biquad.reset();
+                self.biquad.snap();
+            }
+        }
+        self.engine_mix.set_target(engine);
+        self.envelope_s.set_target(self.envelope);
+        self.svf.set_cutoff(self.cutoff);
+        self.biquad.update();
+    }
+}
+
+impl Effect for Filter {
+    fn name(&self) -> &'static str {
+        match self.filter_type {
+            FilterType::HighPass => "highpass",
+            FilterType::BandPass => "bandpass",
+            FilterType::Notch => "notch",
+            _ => "lowpass",
+        }
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            self.biquad = CascadedFilter::new(self.filter_type, self.cutoff, sample_rate);
+            self.svf = SvfCascade::new(self.filter_type, self.cutoff, sample_rate);
+            self.primed = false;
+        }
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        self.update();
+        let attack = one_pole(self.attack_ms, self.sample_rate);
+        let release = one_pole(self.release_ms, self.sample_rate);
+        for sample in buffer.iter_mut() {
+            let x = *sample;
+            let rectified = x.abs().min(1.0);
+            let coeff = if rectified > self.level { attack } else { release };
+            self.level = rectified + (self.level - rectified) * coeff;
+            let envelope = self.envelope_s.tick();
+
+            // Only the active engine runs unless switching
+            let engine = self.engine_mix.tick();
+            let biquad_out = if engine < 1.0 { self.biquad.process_sample(x) } else { 0.0 };
+            let svf_out = if engine <= 0.0 {
+                0.0
+            } else if envelope != 0.0 {
+                self.svf.process_sample_scaled(x, (envelope * self.level).exp2())
+            } else {
+                self.svf.process_sample(x)
+            };
+            *sample = biquad_out + (svf_out - biquad_out) * engine;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.biquad.reset();
+        self.svf.reset();
+        self.level = 0.0;
+        self.primed = false;
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "type" => match param_index(value).and_then(FilterType::from_index) {
+                Some(filter_type) if Filter::supports(filter_type) => self.set_filter_type(filter_type),
+                _ => return false,
+            },
+            "cutoff" => self.set_cutoff(value),
+            "q" => self.set_q(value),
+            "slope" => match param_index(value).and_then(FilterSlope::from_index) {
+                Some(slope) => self.set_slope(slope),
+                None => return false,
+            },
+            "alignment" => match param_index(value).and_then(FilterAlignment::from_index) {
+                Some(alignment) => self.set_alignment(alignment),
+                None => return false,
+            },
+            "engine" => match param_index(value).and_then(FilterEngine::from_index) {
+                Some(engine) => self.set_engine(engine),
+                None => return false,
+            },
+            "resonance" => self.set_resonance(value),
+            "envelope" => self.set_envelope(value),
+            "attack" => self.set_attack(value),
+            "release" => self.set_release(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "type" => Some(self.filter_type as u32 as f32),
+            "cutoff" => Some(self.cutoff),
+            "q" => Some(self.q),
+            "slope" => Some(self.slope as u32 as f32),
+            "alignment" => Some(self.alignment as u32 as f32),
+            "engine" => Some(self.engine as u32 as f32),
+            "resonance" => Some(self.resonance),
+            "envelope" => Some(self.envelope),
+            "attack" => Some(self.attack_ms),
+            "release" => Some(self.release_ms),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.biquad.set_smoothing(mode, time_ms);
+        self.svf.set_smoothing(mode, time_ms);
+        self.engine_mix.configure(mode, time_ms, self.sample_rate);
+        self.envelope_s.configure(mode, time_ms, self.sample_rate);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,225 @@
+use std::any::Any;
+use std::f32::consts::FRAC_PI_2;
+
+use super::{param_bool, Effect};
+use crate::delay_line::{DelayInterpolation, DelayLine};
+use crate::lfo::{Lfo, LfoShape};
+use crate::params::UNIT_RANGE;
+use crate::smoothing::{SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+// Delay offset of the swept tap from the dry signal, in ms
+pub const FLANGER_MANUAL_RANGE: (f32, f32) = (0.0, 10.0);
+// Negative feedback inverts the polarity of the recirculated signal
+pub const FLANGER_FEEDBACK_RANGE: (f32, f32) = (-0.95, 0.95);
+
+// Delay swing at full depth, in ms; through-zero mode also delays the dry
+// signal by this much so the swept tap can pass it
+const SWEEP_MS: f32 = 5.0;
+
+// Flanger
+//
+// A short delay swept by a triangle LFO and mixed with the dry signal
+// makes a comb filter whose notches glide up and down. The sweep runs from
+// `manual` up to `manual` + depth * 5 ms. Through-zero mode delays the dry
+// path by 5 ms and sweeps the tap either side of it, so the notches run
+// all the way out to DC and back (at the cost of 5 ms latency).
+// Params: "rate" (Hz), "depth" (0..1), "manual" (ms), "feedback" (-0.95..0.95,
+//         negative inverts polarity), "through_zero" (0/1), "mix" (0..1 equal-power);
+//         read-only "latency" (samples)
+#[derive(Clone, Debug)]
+pub struct Flanger {
+    sample_rate: f32,
+    depth: f32,
+    manual_ms: f32,
+    feedback: f32,
+    through_zero: bool,
+    mix: f32,
+    lfo: Lfo,
+    line: DelayLine,
+    // Dry path delay for through-zero mode
+    dry_line: DelayLine,
+    depth_s: SmoothedValue,
+    manual_s: SmoothedValue,
+    feedback_s: SmoothedValue,
+    mix_s: SmoothedValue,
+    primed: bool,
+}
+
+impl Flanger {
+    pub fn new(sample_rate: f32) -> Flanger {
+        let (line, dry_line) = Flanger::delay_lines(sample_rate);
+        Flanger {
+            sample_rate,
+            depth: 0.7,
+            manual_ms: 1.0,
+            feedback: 0.5,
+            through_zero: false,
+            mix: 0.5,
+            lfo: Lfo::new(LfoShape::Triangle, 0.25, sample_rate),
+            line,
+            dry_line,
+            depth_s: SmoothedValue::new(0.0),
+            manual_s: SmoothedValue::new(0.0),
+            feedback_s: SmoothedValue::new(0.0),
+            mix_s: SmoothedValue::new(0.0),
+            primed: false,
+        }
+    }
+
+    fn delay_lines(sample_rate: f32) -> (DelayLine, DelayLine) {
+        let samples = |ms: f32| (ms * 0.001 * sample_rate).ceil() as usize + 2;
+        let mut line = DelayLine::new(samples(FLANGER_MANUAL_RANGE.1 + 2.0 * SWEEP_MS));
+        line.set_interpolation(DelayInterpolation::Cubic);
+        (line, DelayLine::new(samples(SWEEP_MS)))
+    }
+
+    pub fn set_rate(&mut self, hz: f32) {
+        self.lfo.set_rate(hz);
+    }
+
+    pub fn set_depth(&mut self, depth: f32) {
+        if depth.is_finite() {
+            self.depth = depth.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    // Where the sweep starts (or, through zero, where it is centred), in ms
+    pub fn set_manual(&mut self, ms: f32) {
+        if ms.is_finite() {
+            self.manual_ms = ms.clamp(FLANGER_MANUAL_RANGE.0, FLANGER_MANUAL_RANGE.1);
+        }
+    }
+
+    pub fn set_feedback(&mut self, feedback: f32) {
+        if feedback.is_finite() {
+            self.feedback = feedback.clamp(FLANGER_FEEDBACK_RANGE.0, FLANGER_FEEDBACK_RANGE.1);
+        }
+    }
+
+    // Switching changes the latency, so it is meant to be set up front
+    pub fn set_through_zero(&mut self, through_zero: bool) {
+        if through_zero != self.through_zero {
+            self.through_zero = through_zero;
+            self.dry_line.reset();
+        }
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        if mix.is_finite() {
+            self.mix = mix.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    fn dry_delay(&self) -> f32 {
+        SWEEP_MS * 0.001 * self.sample_rate
+    }
+}
+
+impl Effect for Flanger {
+    fn name(&self) -> &'static str {
+        "flanger"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            (self.line, self.dry_line) = Flanger::delay_lines(sample_rate);
+            self.lfo.set_sample_rate(sample_rate);
+            self.primed = false;
+        }
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        if self.primed {
+            self.depth_s.set_target(self.depth);
+            self.manual_s.set_target(self.manual_ms);
+            self.feedback_s.set_target(self.feedback);
+            self.mix_s.set_target(self.mix);
+        } else {
+            self.depth_s.snap(self.depth);
+            self.manual_s.snap(self.manual_ms);
+            self.feedback_s.snap(self.feedback);
+            self.mix_s.snap(self.mix);
+            self.primed = true;
+        }
+
+        let samples_per_ms = 0.001 * self.sample_rate;
+        let dry_delay = self.dry_delay();
+        for sample in buffer.iter_mut() {
+            let x = *sample;
+            let sweep = self.depth_s.tick() * SWEEP_MS;
+            let manual = self.manual_s.tick();
+            let feedback = self.feedback_s.tick();
+            let lfo = self.lfo.tick();
+
+            // Delays are counted from the last written sample (1 = newest)
+            let (wet, dry) = if self.through_zero {
+                let offset = manual + sweep * lfo;
+                let wet = self.line.read_at(1.0 + dry_delay + offset * samples_per_ms);
+                let dry = self.dry_line.read_at(1.0 + dry_delay);
+                self.dry_line.write(x);
+                (wet, dry)
+            } else {
+                let wet = self.line.read_at(1.0 + (manual + sweep * 0.5 * (1.0 + lfo)) * samples_per_ms);
+                (wet, x)
+            };
+            self.line.write(x + feedback * wet);
+
+            let (wet_gain, dry_gain) = (self.mix_s.tick() * FRAC_PI_2).sin_cos();
+            *sample = dry * dry_gain + wet * wet_gain;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.line.reset();
+        self.dry_line.reset();
+        self.lfo.reset();
+        self.primed = false;
+    }
+
+    fn latency(&self) -> usize {
+        if self.through_zero {
+            self.dry_delay().round() as usize + 1
+        } else {
+            0
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "rate" => self.set_rate(value),
+            "depth" => self.set_depth(value),
+            "manual" => self.set_manual(value),
+            "feedback" => self.set_feedback(value),
+            "through_zero" => self.set_through_zero(param_bool(value)),
+            "mix" => self.set_mix(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "rate" => Some(self.lfo.rate()),
+            "depth" => Some(self.depth),
+            "manual" => Some(self.manual_ms),
+            "feedback" => Some(self.feedback),
+            "through_zero" => Some(if self.through_zero { 1.0 } else { 0.0 }),
+            "mix" => Some(self.mix),
+            "latency" => Some(self.latency() as f32),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.depth_s.configure(mode, time_ms, self.sample_rate);
+        self.manual_s.configure(mode, time_ms, self.sample_rate);
+        self.feedback_s.configure(mode, time_ms, self.sample_rate);
+        self.mix_s.configure(mode, time_ms, self.sample_rate);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
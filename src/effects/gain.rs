@@ -0,0 +1,92 @@
+use std::any::Any;
+
+use super::Effect;
+use crate::params::GAIN_RANGE;
+use crate::smoothing::{SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+// Linear gain stage
+// Params: "gain" (linear, 0..4)
+#[derive(Clone, Debug)]
+pub struct Gain {
+    sample_rate: f32,
+    gain: f32,
+    smoothed: SmoothedValue,
+    primed: bool,
+}
+
+impl Gain {
+    pub fn new() -> Gain {
+        Gain {
+            sample_rate: 48000.0,
+            gain: 1.0,
+            smoothed: SmoothedValue::new(1.0),
+            primed: false,
+        }
+    }
+
+    pub fn set_gain(&mut self, gain: f32) {
+        if gain.is_finite() {
+            self.gain = gain.clamp(GAIN_RANGE.0, GAIN_RANGE.1);
+        }
+    }
+
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+}
+
+impl Default for Gain {
+    fn default() -> Self {
+        Gain::new()
+    }
+}
+
+impl Effect for Gain {
+    fn name(&self) -> &'static str {
+        "gain"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.sample_rate = sample_rate;
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        if self.primed {
+            self.smoothed.set_target(self.gain);
+        } else {
+            self.smoothed.snap(self.gain);
+            self.primed = true;
+        }
+        for sample in buffer.iter_mut() {
+            *sample *= self.smoothed.tick();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.primed = false;
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "gain" => self.set_gain(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "gain" => Some(self.gain),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.smoothed.configure(mode, time_ms, self.sample_rate);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
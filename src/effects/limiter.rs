@@ -0,0 +1,342 @@
+use std::any::Any;
+use std::f32::consts::PI;
+
+use super::Effect;
+use crate::smoothing::{db_to_gain, gain_to_db, one_pole};
+
+pub const LIMITER_CEILING_RANGE: (f32, f32) = (-24.0, 0.0);
+pub const LIMITER_RELEASE_RANGE: (f32, f32) = (1.0, 1000.0);
+pub const LIMITER_LOOKAHEAD_RANGE: (f32, f32) = (0.0, 10.0);
+
+// Taps of each true-peak interpolation phase; the detector runs this
+// many / 2 samples behind the input
+const TP_TAPS: usize = 8;
+const TP_DELAY: usize = TP_TAPS / 2;
+// Points estimated between two samples (4x oversampled detection)
+const TP_PHASES: usize = 3;
+
+// Sliding minimum over the last `len` values (monotonic queue, O(1) amortised)
+#[derive(Clone, Debug)]
+struct MinWindow {
+    // (arrival count, value) with values increasing from front to back
+    queue: Vec<(usize, f32)>,
+    head: usize,
+    count: usize,
+    time: usize,
+    len: usize,
+}
+
+impl MinWindow {
+    fn new(capacity: usize) -> MinWindow {
+        MinWindow { queue: vec![(0, 0.0); capacity], head: 0, count: 0, time: 0, len: 1 }
+    }
+
+    fn clear(&mut self, len: usize) {
+        self.len = len.clamp(1, self.queue.len());
+        self.head = 0;
+        self.count = 0;
+    }
+
+    fn push(&mut self, value: f32) -> f32 {
+        let cap = self.queue.len();
+        while self.count > 0 && self.queue[(self.head + self.count - 1) % cap].1 >= value {
+            self.count -= 1;
+        }
+        self.queue[(self.head + self.count) % cap] = (self.time, value);
+        self.count += 1;
+        while self.queue[self.head].0 + self.len <= self.time {
+            self.head = (self.head + 1) % cap;
+            self.count -= 1;
+        }
+        self.time += 1;
+        self.queue[self.head].1
+    }
+}
+
+// Moving average over the last `len` values
+#[derive(Clone, Debug)]
+struct Boxcar {
+    values: Vec<f32>,
+    pos: usize,
+    len: usize,
+    sum: f64,
+}
+
+impl Boxcar {
+    fn new(capacity: usize) -> Boxcar {
+        Boxcar { values: vec![1.0; capacity], pos: 0, len: 1, sum: 1.0 }
+    }
+
+    // Restart filled with unity gain
+    fn clear(&mut self, len: usize) {
+        self.len = len.clamp(1, self.values.len());
+        self.values[..self.len].fill(1.0);
+        self.pos = 0;
+        self.sum = self.len as f64;
+    }
+
+    fn push(&mut self, value: f32) -> f32 {
+        self.sum += value as f64 - self.values[self.pos] as f64;
+        self.values[self.pos] = value;
+        self.pos = (self.pos + 1) % self.len;
+        (self.sum / self.len as f64) as f32
+    }
+}
+
+// One channel's true-peak detector history and output delay line
+#[derive(Clone, Debug)]
+struct LimiterChannel {
+    history: [f32; TP_TAPS],
+    delay: Vec<f32>,
+}
+
+impl LimiterChannel {
+    fn new(delay_capacity: usize) -> LimiterChannel {
+        LimiterChannel { history: [0.0; TP_TAPS], delay: vec![0.0; delay_capacity] }
+    }
+
+    // Push one sample and return the true-peak estimate around the sample
+    // TP_DELAY steps back: the sample itself and three interpolated points
+    // after it
+    #[inline]
+    fn detect(&mut self, x: f32, phases: &[[f32; TP_TAPS]; TP_PHASES]) -> f32 {
+        self.history.copy_within(1.., 0);
+        self.history[TP_TAPS - 1] = x;
+        let mut peak = self.history[TP_DELAY - 1].abs();
+        for phase in phases.iter() {
+            let y: f32 = phase.iter().zip(self.history.iter()).map(|(h, x)| h * x).sum();
+            peak = peak.max(y.abs());
+        }
+        peak
+    }
+
+    fn clear(&mut self) {
+        self.history = [0.0; TP_TAPS];
+        self.delay.fill(0.0);
+    }
+}
+
+// Look-ahead brick-wall limiter with true-peak detection
+//
+// The detector estimates inter-sample peaks with a 4x polyphase
+// interpolator. The gain needed to keep each peak under the ceiling is
+// held for the look-ahead time, released with a one-pole and averaged
+// over the look-ahead window, so the gain has fully come down by the
+// time the delayed peak is output instead of clipping it. A final clamp
+// at the ceiling catches the few samples the interpolator underestimates.
+// Stereo pairs share the gain so the image does not move.
+// Params: "ceiling" (dBFS), "release" (ms), "lookahead" (ms);
+//         read-only "gain_reduction" (dB), "latency" (samples)
+#[derive(Clone, Debug)]
+pub struct Limiter {
+    sample_rate: f32,
+    ceiling_db: f32,
+    release_ms: f32,
+    lookahead_ms: f32,
+    // Look-ahead in samples (the gain window is one longer)
+    lookahead: usize,
+    phases: [[f32; TP_TAPS]; TP_PHASES],
+    left: LimiterChannel,
+    right: LimiterChannel,
+    write: usize,
+    hold: MinWindow,
+    average: Boxcar,
+    released: f32,
+    gain: f32,
+}
+
+impl Limiter {
+    pub fn new(sample_rate: f32) -> Limiter {
+        let capacity = Limiter::max_lookahead(sample_rate) + TP_DELAY + 1;
+        let mut limiter = Limiter {
+            sample_rate,
+            ceiling_db: -0.5,
+            release_ms: 50.0,
+            lookahead_ms: 1.5,
+            lookahead: 0,
+            phases: interpolation_phases(),
+            left: LimiterChannel::new(capacity),
+            right: LimiterChannel::new(capacity),
+            write: 0,
+            hold: MinWindow::new(capacity),
+            average: Boxcar::new(capacity),
+            released: 1.0,
+            gain: 1.0,
+        };
+        limiter.update_lookahead();
+        limiter
+    }
+
+    fn max_lookahead(sample_rate: f32) -> usize {
+        (LIMITER_LOOKAHEAD_RANGE.1 * 0.001 * sample_rate).ceil() as usize
+    }
+
+    // Highest output level in dBFS
+    pub fn set_ceiling(&mut self, db: f32) {
+        if db.is_finite() {
+            self.ceiling_db = db.clamp(LIMITER_CEILING_RANGE.0, LIMITER_CEILING_RANGE.1);
+        }
+    }
+
+    pub fn set_release(&mut self, time_ms: f32) {
+        if time_ms.is_finite() {
+            self.release_ms = time_ms.clamp(LIMITER_RELEASE_RANGE.0, LIMITER_RELEASE_RANGE.1);
+        }
+    }
+
+    // Changing the look-ahead changes the latency and restarts the gain
+    // envelope, so it is meant to be set up front rather than automated
+    pub fn set_lookahead(&mut self, time_ms: f32) {
+        if time_ms.is_finite() {
+            self.lookahead_ms = time_ms.clamp(LIMITER_LOOKAHEAD_RANGE.0, LIMITER_LOOKAHEAD_RANGE.1);
+            self.update_lookahead();
+        }
+    }
+
+    // Current gain reduction in dB (positive)
+    pub fn gain_reduction(&self) -> f32 {
+        (-gain_to_db(self.gain)).max(0.0)
+    }
+
+    fn update_lookahead(&mut self) {
+        let lookahead = ((self.lookahead_ms * 0.001 * self.sample_rate).round() as usize)
+            .min(Limiter::max_lookahead(self.sample_rate));
+        if lookahead != self.lookahead {
+            self.lookahead = lookahead;
+            self.clear_envelope();
+        }
+    }
+
+    fn clear_envelope(&mut self) {
+        self.hold.clear(self.lookahead + 1);
+        self.average.clear(self.lookahead + 1);
+        self.released = 1.0;
+        self.gain = 1.0;
+    }
+
+    // Gain for the sample leaving the delay line, from the newest peak
+    #[inline]
+    fn envelope(&mut self, peak: f32, ceiling: f32, release: f32) -> f32 {
+        let needed = if peak > ceiling { ceiling / peak } else { 1.0 };
+        let held = self.hold.push(needed);
+        self.released = if held < self.released {
+            held
+        } else {
+            held + (self.released - held) * release
+        };
+        self.gain = self.average.push(self.released);
+        self.gain
+    }
+
+    fn delay_len(&self) -> usize {
+        self.lookahead + TP_DELAY
+    }
+
+    fn block_constants(&self) -> (f32, f32) {
+        (db_to_gain(self.ceiling_db), one_pole(self.release_ms, self.sample_rate))
+    }
+}
+
+// Windowed-sinc taps for the points 1/4, 2/4 and 3/4 of the way from the
+// sample at TP_DELAY - 1 to the next one
+fn interpolation_phases() -> [[f32; TP_TAPS]; TP_PHASES] {
+    std::array::from_fn(|p| {
+        let frac = (p + 1) as f32 / (TP_PHASES + 1) as f32;
+        let taps: [f32; TP_TAPS] = std::array::from_fn(|k| {
+            let t = k as f32 - (TP_DELAY - 1) as f32 - frac;
+            let sinc = if t.abs() < 1e-6 { 1.0 } else { (PI * t).sin() / (PI * t) };
+            let window = 0.5 + 0.5 * (PI * t / (TP_DELAY as f32 + 1.0)).cos();
+            sinc * window
+        });
+        // Unity gain at DC
+        let sum: f32 = taps.iter().sum();
+        taps.map(|h| h / sum)
+    })
+}
+
+impl Effect for Limiter {
+    fn name(&self) -> &'static str {
+        "limiter"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        if sample_rate != self.sample_rate {
+            let mut fresh = Limiter::new(sample_rate);
+            fresh.ceiling_db = self.ceiling_db;
+            fresh.release_ms = self.release_ms;
+            fresh.set_lookahead(self.lookahead_ms);
+            *self = fresh;
+        }
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        let (ceiling, release) = self.block_constants();
+        let cap = self.left.delay.len();
+        let delay = self.delay_len();
+        for sample in buffer.iter_mut() {
+            let peak = self.left.detect(*sample, &self.phases);
+            self.left.delay[self.write] = *sample;
+            let out = self.left.delay[(self.write + cap - delay) % cap];
+            self.write = (self.write + 1) % cap;
+            let gain = self.envelope(peak, ceiling, release);
+            *sample = (out * gain).clamp(-ceiling, ceiling);
+        }
+    }
+
+    fn is_stereo(&self) -> bool {
+        true
+    }
+
+    fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let (ceiling, release) = self.block_constants();
+        let cap = self.left.delay.len();
+        let delay = self.delay_len();
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            let peak = self.left.detect(*l, &self.phases).max(self.right.detect(*r, &self.phases));
+            self.left.delay[self.write] = *l;
+            self.right.delay[self.write] = *r;
+            let read = (self.write + cap - delay) % cap;
+            let (out_l, out_r) = (self.left.delay[read], self.right.delay[read]);
+            self.write = (self.write + 1) % cap;
+            let gain = self.envelope(peak, ceiling, release);
+            *l = (out_l * gain).clamp(-ceiling, ceiling);
+            *r = (out_r * gain).clamp(-ceiling, ceiling);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.left.clear();
+        self.right.clear();
+        self.write = 0;
+        self.clear_envelope();
+    }
+
+    fn latency(&self) -> usize {
+        self.delay_len()
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "ceiling" => self.set_ceiling(value),
+            "release" => self.set_release(value),
+            "lookahead" => self.set_lookahead(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "ceiling" => Some(self.ceiling_db),
+            "release" => Some(self.release_ms),
+            "lookahead" => Some(self.lookahead_ms),
+            "gain_reduction" => Some(self.gain_reduction()),
+            "latency" => Some(self.latency() as f32),
+            _ => None,
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,183 @@
+use std::any::Any;
+
+use wasm_bindgen::prelude::*;
+
+use crate::oversampling::{Oversampled, DEFAULT_OVERSAMPLING};
+use crate::smoothing::SmoothingMode;
+
+pub mod auto_pan;
+pub mod chorus;
+pub mod clipper;
+pub mod compressor;
+pub mod convolver;
+pub mod delay;
+pub mod distortion;
+pub mod equalizer;
+pub mod filter;
+pub mod flanger;
+pub mod gain;
+pub mod limiter;
+pub mod noise_gate;
+pub mod phaser;
+pub mod pitch_shifter;
+pub mod reverb;
+pub mod ring_modulator;
+pub mod stereo_delay;
+pub mod tremolo;
+pub mod waveshaper;
+
+pub use auto_pan::AutoPan;
+pub use chorus::Chorus;
+pub use clipper::Clipper;
+pub use compressor::{Compressor, DetectorMode};
+pub use convolver::Convolver;
+pub use delay::Delay;
+pub use distortion::Distortion;
+pub use equalizer::ParametricEq;
+pub use filter::Filter;
+pub use flanger::Flanger;
+pub use gain::Gain;
+pub use limiter::Limiter;
+pub use noise_gate::NoiseGate;
+pub use phaser::Phaser;
+pub use pitch_shifter::PitchShifter;
+pub use reverb::Reverb;
+pub use ring_modulator::RingModulator;
+pub use stereo_delay::{StereoDelay, StereoDelayMode};
+pub use tremolo::Tremolo;
+pub use waveshaper::{Waveshaper, WaveshaperCurve};
+
+// One processing stage of an EffectChain
+//
+// Effects smooth their own parameters and must be real-time safe in
+// process(): no allocation once prepare() has run.
+// The chain runs one instance per channel, so an effect only ever sees
+// a single channel and keeps that channel's state - unless it is a
+// stereo effect, which gets one instance per channel pair.
+pub trait Effect: EffectClone {
+    // Short identifier shown in the UI (e.g. "gain", "delay")
+    fn name(&self) -> &'static str;
+
+    // Called before the first process() and whenever the sample rate
+    // or the largest block size changes
+    fn prepare(&mut self, sample_rate: f32, max_block: usize);
+
+    // Process a mono block in place (never longer than max_block)
+    // Stereo effects also get this for a lone channel (mono input or the
+    // last channel of an odd count)
+    fn process(&mut self, buffer: &mut [f32]);
+
+    // Effects that mix their channels (ping-pong delay, reverb...) return
+    // true and receive channel pairs through process_stereo()
+    fn is_stereo(&self) -> bool {
+        false
+    }
+
+    // Process a left/right pair in place; only called when is_stereo()
+    fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        self.process(left);
+        self.process(right);
+    }
+
+    // Clear all internal state (filter history, delay buffers...)
+    fn reset(&mut self);
+
+    // Delay this effect adds to the signal, in samples
+    fn latency(&self) -> usize {
+        0
+    }
+
+    // Named parameters for the handle-based JS API
+    // Returns false for unknown names
+    fn set_param(&mut self, _name: &str, _value: f32) -> bool {
+        false
+    }
+
+    fn get_param(&self, _name: &str) -> Option<f32> {
+        None
+    }
+
+    fn set_smoothing(&mut self, _mode: SmoothingMode, _time_ms: f32) {}
+
+    // Typed access for settings that do not fit set_param (EQ bands...)
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+// Lets the chain make extra per-channel instances of any Clone effect
+pub trait EffectClone {
+    fn box_clone(&self) -> Box<dyn Effect>;
+}
+
+impl<T: Effect + Clone + 'static> EffectClone for T {
+    fn box_clone(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+}
+
+// Effects that can be created from JS
+// The nonlinear ones (distortion, waveshaper) come wrapped in Oversampled
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EffectKind {
+    Gain,
+    Distortion,
+    LowPass,
+    HighPass,
+    Equalizer,
+    Delay,
+    Clipper,
+    StereoDelay,
+    Reverb,
+    Convolver,
+    NoiseGate,
+    Compressor,
+    Limiter,
+    Waveshaper,
+    Chorus,
+    Flanger,
+    Phaser,
+    Tremolo,
+    AutoPan,
+    RingModulator,
+    PitchShifter,
+}
+
+pub fn create_effect(kind: EffectKind, sample_rate: f32) -> Box<dyn Effect> {
+    match kind {
+        EffectKind::Gain => Box::new(Gain::new()),
+        EffectKind::Distortion => Box::new(Oversampled::new(Distortion::new(), DEFAULT_OVERSAMPLING)),
+        EffectKind::LowPass => Box::new(Filter::low_pass(sample_rate)),
+        EffectKind::HighPass => Box::new(Filter::high_pass(sample_rate)),
+        EffectKind::Equalizer => Box::new(ParametricEq::new(sample_rate)),
+        EffectKind::Delay => Box::new(Delay::new(sample_rate)),
+        EffectKind::Clipper => Box::new(Clipper::new()),
+        EffectKind::StereoDelay => Box::new(StereoDelay::new(sample_rate)),
+        EffectKind::Reverb => Box::new(Reverb::new(sample_rate)),
+        EffectKind::Convolver => Box::new(Convolver::new(sample_rate)),
+        EffectKind::NoiseGate => Box::new(NoiseGate::new(sample_rate)),
+        EffectKind::Compressor => Box::new(Compressor::new(sample_rate)),
+        EffectKind::Limiter => Box::new(Limiter::new(sample_rate)),
+        EffectKind::Waveshaper => Box::new(Oversampled::new(Waveshaper::new(sample_rate), DEFAULT_OVERSAMPLING)),
+        EffectKind::Chorus => Box::new(Chorus::new(sample_rate)),
+        EffectKind::Flanger => Box::new(Flanger::new(sample_rate)),
+        EffectKind::Phaser => Box::new(Phaser::new(sample_rate)),
+        EffectKind::Tremolo => Box::new(Tremolo::new(sample_rate)),
+        EffectKind::AutoPan => Box::new(AutoPan::new(sample_rate)),
+        EffectKind::RingModulator => Box::new(RingModulator::new(sample_rate)),
+        EffectKind::PitchShifter => Box::new(PitchShifter::new(sample_rate)),
+    }
+}
+
+// Parameter values arrive from JS as f32; booleans are > 0.5
+pub(crate) fn param_bool(value: f32) -> bool {
+    value > 0.5
+}
+
+// Enum parameters are passed as their index
+pub(crate) fn param_index(value: f32) -> Option<usize> {
+    if value.is_finite() && value >= 0.0 {
+        Some(value.round() as usize)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,265 @@
+use std::any::Any;
+
+use super::Effect;
+use crate::biquad::{Biquad, FilterType};
+use crate::smoothing::{db_to_gain, gain_to_db, one_pole};
+
+// Threshold at the bottom of the range leaves the gate open (off)
+pub const GATE_THRESHOLD_RANGE: (f32, f32) = (-100.0, 0.0);
+pub const GATE_HYSTERESIS_RANGE: (f32, f32) = (0.0, 24.0);
+pub const GATE_ATTACK_RANGE: (f32, f32) = (0.1, 100.0);
+pub const GATE_HOLD_RANGE: (f32, f32) = (0.0, 2000.0);
+pub const GATE_RELEASE_RANGE: (f32, f32) = (5.0, 4000.0);
+pub const GATE_RANGE_RANGE: (f32, f32) = (-96.0, 0.0);
+// Sidechain high-pass cutoff; 0 turns the filter off
+pub const GATE_SIDECHAIN_RANGE: (f32, f32) = (20.0, 2000.0);
+
+// Decay of the peak detector - long enough to ride over zero crossings
+const DETECTOR_RELEASE_MS: f32 = 10.0;
+
+// Downward noise gate
+//
+// A peak detector (optionally fed through a sidechain high-pass so rumble
+// does not hold the gate open) opens the gate above the threshold and closes
+// it once the level has stayed `hysteresis` dB lower for the hold time.
+// The gain glides to 1 or to the range floor with the attack/release time constant.
+// Stereo pairs share one detector so both sides open and close together.
+// Params: "threshold" (dBFS, -100 = off), "hysteresis" (dB below threshold to
+//         close), "attack", "hold", "release" (ms), "range" (dB floor when closed),
+//         "sidechain_hpf" (Hz, 0 = off); read-only "gain_reduction" (dB), "open" (0/1)
+#[derive(Clone, Debug)]
+pub struct NoiseGate {
+    sample_rate: f32,
+    threshold_db: f32,
+    hysteresis_db: f32,
+    attack_ms: f32,
+    hold_ms: f32,
+    release_ms: f32,
+    range_db: f32,
+    sidechain_hz: f32,
+    sidechain_left: Biquad,
+    sidechain_right: Biquad,
+    envelope: f32,
+    gain: f32,
+    open: bool,
+    hold_left: usize,
+}
+
+impl NoiseGate {
+    pub fn new(sample_rate: f32) -> NoiseGate {
+        NoiseGate {
+            sample_rate,
+            threshold_db: -60.0,
+            hysteresis_db: 6.0,
+            attack_ms: 1.0,
+            hold_ms: 50.0,
+            release_ms: 150.0,
+            range_db: -80.0,
+            sidechain_hz: 0.0,
+            sidechain_left: Biquad::new(FilterType::HighPass, 80.0, sample_rate),
+            sidechain_right: Biquad::new(FilterType::HighPass, 80.0, sample_rate),
+            envelope: 0.0,
+            gain: 1.0,
+            open: true,
+            hold_left: 0,
+        }
+    }
+
+    pub fn set_threshold(&mut self, db: f32) {
+        if db.is_finite() {
+            self.threshold_db = db.clamp(GATE_THRESHOLD_RANGE.0, GATE_THRESHOLD_RANGE.1);
+        }
+    }
+
+    // How far below the threshold the level must fall to close the gate
+    pub fn set_hysteresis(&mut self, db: f32) {
+        if db.is_finite() {
+            self.hysteresis_db = db.clamp(GATE_HYSTERESIS_RANGE.0, GATE_HYSTERESIS_RANGE.1);
+        }
+    }
+
+    pub fn set_attack(&mut self, time_ms: f32) {
+        if time_ms.is_finite() {
+            self.attack_ms = time_ms.clamp(GATE_ATTACK_RANGE.0, GATE_ATTACK_RANGE.1);
+        }
+    }
+
+    pub fn set_hold(&mut self, time_ms: f32) {
+        if time_ms.is_finite() {
+            self.hold_ms = time_ms.clamp(GATE_HOLD_RANGE.0, GATE_HOLD_RANGE.1);
+        }
+    }
+
+    pub fn set_release(&mut self, time_ms: f32) {
+        if time_ms.is_finite() {
+            self.release_ms = time_ms.clamp(GATE_RELEASE_RANGE.0, GATE_RELEASE_RANGE.1);
+        }
+    }
+
+    // Attenuation while closed, in dB (0 = no gating)
+    pub fn set_range(&mut self, db: f32) {
+        if db.is_finite() {
+            self.range_db = db.clamp(GATE_RANGE_RANGE.0, GATE_RANGE_RANGE.1);
+        }
+    }
+
+    // Sidechain high-pass cutoff in Hz, 0 to disable
+    pub fn set_sidechain_hpf(&mut self, hz: f32) {
+        if !hz.is_finite() {
+            return;
+        }
+        self.sidechain_hz = if hz <= 0.0 {
+            0.0
+        } else {
+            hz.clamp(GATE_SIDECHAIN_RANGE.0, GATE_SIDECHAIN_RANGE.1)
+        };
+        if self.sidechain_hz > 0.0 {
+            self.sidechain_left.set_frequency(self.sidechain_hz);
+            self.sidechain_right.set_frequency(self.sidechain_hz);
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    // Current attenuation in dB (positive)
+    pub fn gain_reduction(&self) -> f32 {
+        (-gain_to_db(self.gain)).max(0.0)
+    }
+
+    // Advance the detector and gain by one sample of sidechain level
+    #[inline]
+    fn tick(&mut self, level: f32, c: &Coeffs) -> f32 {
+        self.envelope = if level > self.envelope { level } else { self.envelope * c.detector };
+
+        if !c.enabled || self.envelope >= c.open || (self.open && self.envelope >= c.close) {
+            self.open = true;
+            self.hold_left = c.hold;
+        } else if self.hold_left > 0 {
+            self.hold_left -= 1;
+        } else {
+            self.open = false;
+        }
+
+        let (target, coeff) = if self.open { (1.0, c.attack) } else { (c.floor, c.release) };
+        self.gain = target + (self.gain - target) * coeff;
+        self.gain
+    }
+
+    fn coeffs(&mut self) -> Coeffs {
+        let sr = self.sample_rate;
+        if self.sidechain_hz > 0.0 {
+            self.sidechain_left.update();
+            self.sidechain_right.update();
+        }
+        let open = db_to_gain(self.threshold_db);
+        Coeffs {
+            enabled: self.threshold_db > GATE_THRESHOLD_RANGE.0,
+            open,
+            close: open * db_to_gain(-self.hysteresis_db),
+            floor: db_to_gain(self.range_db),
+            hold: (self.hold_ms * 0.001 * sr) as usize,
+            detector: one_pole(DETECTOR_RELEASE_MS, sr),
+            attack: one_pole(self.attack_ms, sr),
+            release: one_pole(self.release_ms, sr),
+        }
+    }
+}
+
+// Per-block constants for tick()
+struct Coeffs {
+    enabled: bool,
+    open: f32,
+    close: f32,
+    floor: f32,
+    hold: usize,
+    detector: f32,
+    attack: f32,
+    release: f32,
+}
+
+impl Effect for NoiseGate {
+    fn name(&self) -> &'static str {
+        "noise_gate"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.sample_rate = sample_rate;
+        self.sidechain_left.set_sample_rate(sample_rate);
+        self.sidechain_right.set_sample_rate(sample_rate);
+        self.sidechain_left.snap();
+        self.sidechain_right.snap();
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        let c = self.coeffs();
+        let filtered = self.sidechain_hz > 0.0;
+        for sample in buffer.iter_mut() {
+            let x = *sample;
+            let key = if filtered { self.sidechain_left.process_sample(x) } else { x };
+            *sample = x * self.tick(key.abs(), &c);
+        }
+    }
+
+    fn is_stereo(&self) -> bool {
+        true
+    }
+
+    fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let c = self.coeffs();
+        let filtered = self.sidechain_hz > 0.0;
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            let (key_l, key_r) = if filtered {
+                (self.sidechain_left.process_sample(*l), self.sidechain_right.process_sample(*r))
+            } else {
+                (*l, *r)
+            };
+            let gain = self.tick(key_l.abs().max(key_r.abs()), &c);
+            *l *= gain;
+            *r *= gain;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.sidechain_left.reset();
+        self.sidechain_right.reset();
+        self.envelope = 0.0;
+        self.gain = 1.0;
+        self.open = true;
+        self.hold_left = 0;
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "threshold" => self.set_threshold(value),
+            "hysteresis" => self.set_hysteresis(value),
+            "attack" => self.set_attack(value),
+            "hold" => self.set_hold(value),
+            "release" => self.set_release(value),
+            "range" => self.set_range(value),
+            "sidechain_hpf" => self.set_sidechain_hpf(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "threshold" => Some(self.threshold_db),
+            "hysteresis" => Some(self.hysteresis_db),
+            "attack" => Some(self.attack_ms),
+            "hold" => Some(self.hold_ms),
+            "release" => Some(self.release_ms),
+            "range" => Some(self.range_db),
+            "sidechain_hpf" => Some(self.sidechain_hz),
+            "gain_reduction" => Some(self.gain_reduction()),
+            "open" => Some(if self.open { 1.0 } else { 0.0 }),
+            _ => None,
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,268 @@
+use std::any::Any;
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use super::Effect;
+use crate::lfo::{Lfo, LfoShape};
+use crate::params::UNIT_RANGE;
+use crate::smoothing::{SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+pub const PHASER_STAGES_RANGE: (f32, f32) = (2.0, 12.0);
+// Centre of the sweep, in Hz
+pub const PHASER_FREQUENCY_RANGE: (f32, f32) = (50.0, 5000.0);
+// Sweep either side of the centre, in octaves
+pub const PHASER_DEPTH_RANGE: (f32, f32) = (0.0, 4.0);
+// Negative feedback inverts the polarity of the recirculated signal
+pub const PHASER_FEEDBACK_RANGE: (f32, f32) = (-0.95, 0.95);
+// LFO phase of the right channel ahead of the left, in degrees
+pub const PHASER_STEREO_RANGE: (f32, f32) = (0.0, 180.0);
+
+const MAX_STAGES: usize = PHASER_STAGES_RANGE.1 as usize;
+// Lowest swept break frequency
+const MIN_SWEEP_HZ: f32 = 20.0;
+
+// One channel's all-pass chain
+#[derive(Clone, Debug)]
+struct PhaserChannel {
+    states: [f32; MAX_STAGES],
+    // Last output of the chain, fed back into its input
+    last: f32,
+}
+
+impl PhaserChannel {
+    fn new() -> PhaserChannel {
+        PhaserChannel { states: [0.0; MAX_STAGES], last: 0.0 }
+    }
+
+    // Run `x` through `stages` first-order all-passes sharing coefficient `a`
+    #[inline]
+    fn process(&mut self, x: f32, a: f32, stages: usize, feedback: f32) -> f32 {
+        let mut y = x + feedback * self.last;
+        for s in self.states[..stages].iter_mut() {
+            let out = a * y + *s;
+            *s = y - a * out;
+            y = out;
+        }
+        self.last = y;
+        y
+    }
+
+    fn clear(&mut self) {
+        self.states = [0.0; MAX_STAGES];
+        self.last = 0.0;
+    }
+}
+
+// Phaser
+//
+// A chain of first-order all-passes whose break frequency is swept by a
+// sine LFO, mixed back with the dry signal: every two stages make one
+// notch that glides up and down the spectrum. Feedback from the end of
+// the chain sharpens the notches into resonant peaks. The right channel's
+// LFO runs `stereo` degrees ahead so the sweep moves across the image.
+// Params: "stages" (2..12), "rate" (Hz), "frequency" (sweep centre Hz),
+//         "depth" (octaves either side), "feedback" (-0.95..0.95, negative
+//         inverts polarity), "stereo" (degrees), "mix" (0..1 equal-power)
+#[derive(Clone, Debug)]
+pub struct Phaser {
+    sample_rate: f32,
+    stages: usize,
+    frequency: f32,
+    depth: f32,
+    feedback: f32,
+    stereo_deg: f32,
+    mix: f32,
+    lfo: Lfo,
+    left: PhaserChannel,
+    right: PhaserChannel,
+    frequency_s: SmoothedValue,
+    depth_s: SmoothedValue,
+    feedback_s: SmoothedValue,
+    mix_s: SmoothedValue,
+    primed: bool,
+}
+
+impl Phaser {
+    pub fn new(sample_rate: f32) -> Phaser {
+        Phaser {
+            sample_rate,
+            stages: 4,
+            frequency: 800.0,
+            depth: 2.0,
+            feedback: 0.5,
+            stereo_deg: 90.0,
+            mix: 0.5,
+            lfo: Lfo::new(LfoShape::Sine, 0.5, sample_rate),
+            left: PhaserChannel::new(),
+            right: PhaserChannel::new(),
+            frequency_s: SmoothedValue::new(0.0),
+            depth_s: SmoothedValue::new(0.0),
+            feedback_s: SmoothedValue::new(0.0),
+            mix_s: SmoothedValue::new(0.0),
+            primed: false,
+        }
+    }
+
+    pub fn set_stages(&mut self, stages: f32) {
+        if !stages.is_finite() {
+            return;
+        }
+        let stages = stages.round().clamp(PHASER_STAGES_RANGE.0, PHASER_STAGES_RANGE.1) as usize;
+        // Stages coming back in start from silence, not stale state
+        for channel in [&mut self.left, &mut self.right] {
+            for s in channel.states[self.stages.min(stages)..].iter_mut() {
+                *s = 0.0;
+            }
+        }
+        self.stages = stages;
+    }
+
+    pub fn set_rate(&mut self, hz: f32) {
+        self.lfo.set_rate(hz);
+    }
+
+    pub fn set_frequency(&mut self, hz: f32) {
+        if hz.is_finite() {
+            self.frequency = hz.clamp(PHASER_FREQUENCY_RANGE.0, PHASER_FREQUENCY_RANGE.1);
+        }
+    }
+
+    pub fn set_depth(&mut self, octaves: f32) {
+        if octaves.is_finite() {
+            self.depth = octaves.clamp(PHASER_DEPTH_RANGE.0, PHASER_DEPTH_RANGE.1);
+        }
+    }
+
+    pub fn set_feedback(&mut self, feedback: f32) {
+        if feedback.is_finite() {
+            self.feedback = feedback.clamp(PHASER_FEEDBACK_RANGE.0, PHASER_FEEDBACK_RANGE.1);
+        }
+    }
+
+    pub fn set_stereo(&mut self, degrees: f32) {
+        if degrees.is_finite() {
+            self.stereo_deg = degrees.clamp(PHASER_STEREO_RANGE.0, PHASER_STEREO_RANGE.1);
+        }
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        if mix.is_finite() {
+            self.mix = mix.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    fn prime(&mut self) {
+        if self.primed {
+            self.frequency_s.set_target(self.frequency);
+            self.depth_s.set_target(self.depth);
+            self.feedback_s.set_target(self.feedback);
+            self.mix_s.set_target(self.mix);
+        } else {
+            self.frequency_s.snap(self.frequency);
+            self.depth_s.snap(self.depth);
+            self.feedback_s.snap(self.feedback);
+            self.mix_s.snap(self.mix);
+            self.primed = true;
+        }
+    }
+
+    // All-pass coefficient for an LFO value
+    #[inline]
+    fn coefficient(&self, frequency: f32, depth: f32, lfo: f32) -> f32 {
+        let hz = (frequency * (depth * lfo).exp2()).clamp(MIN_SWEEP_HZ, 0.45 * self.sample_rate);
+        let t = (PI * hz / self.sample_rate).tan();
+        (t - 1.0) / (t + 1.0)
+    }
+}
+
+impl Effect for Phaser {
+    fn name(&self) -> &'static str {
+        "phaser"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.sample_rate = sample_rate;
+        self.lfo.set_sample_rate(sample_rate);
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        self.prime();
+        for sample in buffer.iter_mut() {
+            let frequency = self.frequency_s.tick();
+            let depth = self.depth_s.tick();
+            let feedback = self.feedback_s.tick();
+            let lfo = self.lfo.tick();
+            let a = self.coefficient(frequency, depth, lfo);
+            let wet = self.left.process(*sample, a, self.stages, feedback);
+            let (wet_gain, dry_gain) = (self.mix_s.tick() * FRAC_PI_2).sin_cos();
+            *sample = *sample * dry_gain + wet * wet_gain;
+        }
+    }
+
+    fn is_stereo(&self) -> bool {
+        true
+    }
+
+    fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        self.prime();
+        let offset = self.stereo_deg / 360.0;
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            let frequency = self.frequency_s.tick();
+            let depth = self.depth_s.tick();
+            let feedback = self.feedback_s.tick();
+            let a_l = self.coefficient(frequency, depth, self.lfo.value_at(0.0));
+            let a_r = self.coefficient(frequency, depth, self.lfo.value_at(offset));
+            self.lfo.advance();
+            let wet_l = self.left.process(*l, a_l, self.stages, feedback);
+            let wet_r = self.right.process(*r, a_r, self.stages, feedback);
+            let (wet_gain, dry_gain) = (self.mix_s.tick() * FRAC_PI_2).sin_cos();
+            *l = *l * dry_gain + wet_l * wet_gain;
+            *r = *r * dry_gain + wet_r * wet_gain;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.left.clear();
+        self.right.clear();
+        self.lfo.reset();
+        self.primed = false;
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "stages" => self.set_stages(value),
+            "rate" => self.set_rate(value),
+            "frequency" => self.set_frequency(value),
+            "depth" => self.set_depth(value),
+            "feedback" => self.set_feedback(value),
+            "stereo" => self.set_stereo(value),
+            "mix" => self.set_mix(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "stages" => Some(self.stages as f32),
+            "rate" => Some(self.lfo.rate()),
+            "frequency" => Some(self.frequency),
+            "depth" => Some(self.depth),
+            "feedback" => Some(self.feedback),
+            "stereo" => Some(self.stereo_deg),
+            "mix" => Some(self.mix),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.frequency_s.configure(mode, time_ms, self.sample_rate);
+        self.depth_s.configure(mode, time_ms, self.sample_rate);
+        self.feedback_s.configure(mode, time_ms, self.sample_rate);
+        self.mix_s.configure(mode, time_ms, self.sample_rate);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
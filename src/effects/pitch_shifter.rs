@@ -0,0 +1,448 @@
+use std::any::Any;
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use super::{param_bool, Effect};
+use crate::biquad::{Biquad, FilterType};
+use crate::delay_line::{DelayInterpolation, DelayLine};
+use crate::params::UNIT_RANGE;
+use crate::smoothing::{SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+pub const PITCH_SEMITONES_RANGE: (f32, f32) = (-24.0, 24.0);
+pub const PITCH_CENTS_RANGE: (f32, f32) = (-100.0, 100.0);
+// Crossfade window of the delay-line shifter, in ms
+pub const PITCH_WINDOW_RANGE: (f32, f32) = (10.0, 100.0);
+
+// Voice range the PSOLA pitch detector looks for
+const MIN_PITCH_HZ: f32 = 70.0;
+const MAX_PITCH_HZ: f32 = 1000.0;
+// The detector runs on input low-passed and decimated to about this rate
+const DETECTOR_RATE: f32 = 12000.0;
+// How often the pitch is re-estimated, in ms
+const DETECTOR_HOP_MS: f32 = 5.0;
+// YIN threshold on the cumulative-mean-normalised difference
+const YIN_THRESHOLD: f32 = 0.15;
+// Mean power below which the input counts as silence (-60 dBFS)
+const SILENCE_POWER: f32 = 1e-6;
+// Grain spacing used for unvoiced input, in ms
+const UNVOICED_PERIOD_MS: f32 = 5.0;
+
+// YIN pitch detector on a decimated copy of the input
+#[derive(Clone, Debug)]
+struct PitchDetector {
+    lowpass: Biquad,
+    decimation: usize,
+    phase: usize,
+    // Decimated ring: one analysis window plus the longest lag
+    history: Vec<f32>,
+    pos: usize,
+    // Oldest-first copy of the history, and the difference function
+    frame: Vec<f32>,
+    diff: Vec<f32>,
+    min_lag: usize,
+    max_lag: usize,
+    hop: usize,
+    until_hop: usize,
+    // Latest estimate in input samples (None = unvoiced or silent)
+    period: Option<f32>,
+}
+
+impl PitchDetector {
+    fn new(sample_rate: f32) -> PitchDetector {
+        let decimation = (sample_rate / DETECTOR_RATE).round().max(1.0) as usize;
+        let rate = sample_rate / decimation as f32;
+        let min_lag = ((rate / MAX_PITCH_HZ).floor() as usize).max(2);
+        let max_lag = (rate / MIN_PITCH_HZ).ceil() as usize;
+        let hop = ((DETECTOR_HOP_MS * 0.001 * sample_rate) as usize).max(1);
+        PitchDetector {
+            lowpass: Biquad::new(FilterType::LowPass, 0.4 * rate, sample_rate),
+            decimation,
+            phase: 0,
+            history: vec![0.0; 2 * max_lag],
+            pos: 0,
+            frame: vec![0.0; 2 * max_lag],
+            diff: vec![0.0; max_lag + 1],
+            min_lag,
+            max_lag,
+            hop,
+            until_hop: hop,
+            period: None,
+        }
+    }
+
+    // Longest period that can be reported, in input samples
+    fn max_period(&self) -> usize {
+        self.max_lag * self.decimation
+    }
+
+    #[inline]
+    fn push(&mut self, x: f32) {
+        let y = self.lowpass.process_sample(x);
+        self.phase += 1;
+        if self.phase == self.decimation {
+            self.phase = 0;
+            self.history[self.pos] = y;
+            self.pos = (self.pos + 1) % self.history.len();
+        }
+        self.until_hop -= 1;
+        if self.until_hop == 0 {
+            self.until_hop = self.hop;
+            self.period = self.estimate();
+        }
+    }
+
+    fn estimate(&mut self) -> Option<f32> {
+        let len = self.history.len();
+        let (older, newer) = self.history.split_at(self.pos);
+        self.frame[..newer.len()].copy_from_slice(newer);
+        self.frame[newer.len()..].copy_from_slice(older);
+
+        let x = &self.frame;
+        let power = x.iter().map(|v| v * v).sum::<f32>() / len as f32;
+        if power < SILENCE_POWER {
+            return None;
+        }
+
+        // Cumulative-mean-normalised squared difference over one window
+        let window = self.max_lag;
+        let mut running = 0.0;
+        self.diff[0] = 1.0;
+        for lag in 1..=self.max_lag {
+            let d: f32 = (0..window).map(|j| (x[j] - x[j + lag]).powi(2)).sum();
+            running += d;
+            self.diff[lag] = if running > 0.0 { d * lag as f32 / running } else { 1.0 };
+        }
+
+        // First dip under the threshold, followed down to its minimum
+        let mut lag = (self.min_lag..self.max_lag).find(|&lag| self.diff[lag] < YIN_THRESHOLD)?;
+        while lag < self.max_lag && self.diff[lag + 1] < self.diff[lag] {
+            lag += 1;
+        }
+        let offset = if lag > 1 && lag < self.max_lag {
+            let (a, b, c) = (self.diff[lag - 1], self.diff[lag], self.diff[lag + 1]);
+            let denom = a - 2.0 * b + c;
+            if denom.abs() > 1e-9 {
+                (0.5 * (a - c) / denom).clamp(-0.5, 0.5)
+            } else {
+                0.0
+            }
+        } else {
+            0.0
+        };
+        Some((lag as f32 + offset) * self.decimation as f32)
+    }
+
+    fn reset(&mut self) {
+        self.lowpass.reset();
+        self.history.fill(0.0);
+        self.pos = 0;
+        self.phase = 0;
+        self.until_hop = self.hop;
+        self.period = None;
+    }
+}
+
+// Pitch-synchronous overlap-add
+//
+// Two-period Hann grains are cut around analysis marks one input period
+// apart and laid down at synthesis marks period / ratio apart, each taken
+// from the analysis mark nearest to it. The grains keep the vocal tract
+// response, so formants stay put while the pitch moves.
+#[derive(Clone, Debug)]
+struct Psola {
+    detector: PitchDetector,
+    input: Vec<f32>,
+    output: Vec<f32>,
+    mask: usize,
+    // Samples written so far (index of the next one)
+    written: usize,
+    // Grain marks, in input samples on the delayed timeline
+    synthesis: f64,
+    analysis: f64,
+    unvoiced_period: f32,
+    // Synthesis runs this far behind the input so whole grains are available
+    lead: usize,
+}
+
+impl Psola {
+    fn new(sample_rate: f32) -> Psola {
+        let detector = PitchDetector::new(sample_rate);
+        let max_period = detector.max_period();
+        let lead = max_period * 3 / 2 + 1;
+        let size = (lead + 2 * max_period + 2).next_power_of_two();
+        Psola {
+            detector,
+            input: vec![0.0; size],
+            output: vec![0.0; size],
+            mask: size - 1,
+            written: 0,
+            synthesis: 0.0,
+            analysis: 0.0,
+            unvoiced_period: (UNVOICED_PERIOD_MS * 0.001 * sample_rate).min(max_period as f32),
+            lead,
+        }
+    }
+
+    fn latency(&self) -> usize {
+        self.lead + self.detector.max_period()
+    }
+
+    #[inline]
+    fn process(&mut self, x: f32, ratio: f32) -> f32 {
+        self.detector.push(x);
+        let newest = self.written;
+        self.input[newest & self.mask] = x;
+        self.written += 1;
+
+        let max_period = self.detector.max_period();
+        let Some(now) = newest.checked_sub(self.lead + max_period) else {
+            return 0.0;
+        };
+        // Lay down every grain due by the delayed "now"
+        let period = self.detector.period.unwrap_or(self.unvoiced_period);
+        let t = (now + max_period) as f64;
+        while self.synthesis <= t {
+            self.add_grain(period);
+            self.synthesis += (period / ratio) as f64;
+        }
+
+        // Everything still to come starts after `now`, so it is final
+        let i = now & self.mask;
+        std::mem::replace(&mut self.output[i], 0.0)
+    }
+
+    fn add_grain(&mut self, period: f32) {
+        let p = period as f64;
+        // Re-sync after a jump in period, then step to the nearest analysis mark
+        if (self.analysis - self.synthesis).abs() > 2.0 * p {
+            self.analysis = self.synthesis;
+        }
+        while self.analysis + 0.5 * p < self.synthesis {
+            self.analysis += p;
+        }
+        while self.analysis - 0.5 * p > self.synthesis {
+            self.analysis -= p;
+        }
+        let half = period.round().max(1.0) as isize;
+        let from = self.analysis.round() as isize;
+        let to = self.synthesis.round() as isize;
+        for k in (1 - half)..half {
+            let w = 0.5 + 0.5 * (PI * k as f32 / half as f32).cos();
+            let src = (from + k) as usize & self.mask;
+            let dst = (to + k) as usize & self.mask;
+            self.output[dst] += w * self.input[src];
+        }
+    }
+
+    fn reset(&mut self) {
+        self.detector.reset();
+        self.input.fill(0.0);
+        self.output.fill(0.0);
+        self.written = 0;
+        self.synthesis = 0.0;
+        self.analysis = 0.0;
+    }
+}
+
+// Real-time pitch shifter
+//
+// Two modes:
+// - Delay (default): two taps sweep through a short delay line at the
+//   rate that gives the new pitch, each faded out as it wraps while the
+//   other, half a window away, takes over. Works on anything, but voices
+//   get the chipmunk/giant formant shift. Latency is half the window.
+// - Formant: PSOLA driven by a YIN pitch detector (70..1000 Hz). Voices
+//   keep their natural timbre; unvoiced input uses short fixed grains.
+//   Latency is about 36 ms.
+// Dry input is not delayed, so mixing gives a harmony/doubling effect.
+// Params: "semitones" (-24..24), "cents" (-100..100), "formant" (0/1),
+//         "window" (ms, delay mode), "mix" (0..1 equal-power);
+//         read-only "latency" (samples), "detected_pitch" (Hz, 0 = unvoiced)
+#[derive(Clone, Debug)]
+pub struct PitchShifter {
+    sample_rate: f32,
+    semitones: f32,
+    cents: f32,
+    formant: bool,
+    window_ms: f32,
+    mix: f32,
+    line: DelayLine,
+    // Position of the first tap in the window, 0..1
+    sweep: f32,
+    psola: Psola,
+    ratio_s: SmoothedValue,
+    mix_s: SmoothedValue,
+    primed: bool,
+}
+
+impl PitchShifter {
+    pub fn new(sample_rate: f32) -> PitchShifter {
+        PitchShifter {
+            sample_rate,
+            semitones: 0.0,
+            cents: 0.0,
+            formant: false,
+            window_ms: 40.0,
+            mix: 1.0,
+            line: PitchShifter::delay_line(sample_rate),
+            sweep: 0.0,
+            psola: Psola::new(sample_rate),
+            ratio_s: SmoothedValue::new(1.0),
+            mix_s: SmoothedValue::new(1.0),
+            primed: false,
+        }
+    }
+
+    fn delay_line(sample_rate: f32) -> DelayLine {
+        let mut line = DelayLine::new((PITCH_WINDOW_RANGE.1 * 0.001 * sample_rate).ceil() as usize + 2);
+        line.set_interpolation(DelayInterpolation::Cubic);
+        line
+    }
+
+    pub fn set_semitones(&mut self, semitones: f32) {
+        if semitones.is_finite() {
+            self.semitones = semitones.clamp(PITCH_SEMITONES_RANGE.0, PITCH_SEMITONES_RANGE.1);
+        }
+    }
+
+    pub fn set_cents(&mut self, cents: f32) {
+        if cents.is_finite() {
+            self.cents = cents.clamp(PITCH_CENTS_RANGE.0, PITCH_CENTS_RANGE.1);
+        }
+    }
+
+    // Switching modes changes the latency, so it is meant to be set up front
+    pub fn set_formant(&mut self, formant: bool) {
+        if formant != self.formant {
+            self.formant = formant;
+            self.line.reset();
+            self.psola.reset();
+        }
+    }
+
+    pub fn set_window(&mut self, time_ms: f32) {
+        if time_ms.is_finite() {
+            self.window_ms = time_ms.clamp(PITCH_WINDOW_RANGE.0, PITCH_WINDOW_RANGE.1);
+        }
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        if mix.is_finite() {
+            self.mix = mix.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    // Frequency ratio of the shift
+    pub fn ratio(&self) -> f32 {
+        ((self.semitones + self.cents / 100.0) / 12.0).exp2()
+    }
+
+    // Pitch the formant mode currently tracks, in Hz (0 = unvoiced)
+    pub fn detected_pitch(&self) -> f32 {
+        match self.psola.detector.period {
+            Some(period) if self.formant => self.sample_rate / period,
+            _ => 0.0,
+        }
+    }
+
+    fn window_samples(&self) -> f32 {
+        self.window_ms * 0.001 * self.sample_rate
+    }
+
+    // Dual crossfaded taps for one sample
+    #[inline]
+    fn shift_delay(&mut self, x: f32, ratio: f32, window: f32) -> f32 {
+        let a = self.sweep;
+        let b = (a + 0.5).fract();
+        // sin^2 + cos^2 = 1: each tap is silent where it wraps
+        let gain = (PI * a).sin().powi(2);
+        let y = self.line.read_at(1.0 + a * window) * gain + self.line.read_at(1.0 + b * window) * (1.0 - gain);
+        self.line.write(x);
+        // Shrinking the delay by (ratio - 1) per sample raises the pitch
+        self.sweep = (a + (1.0 - ratio) / window).rem_euclid(1.0);
+        y
+    }
+}
+
+impl Effect for PitchShifter {
+    fn name(&self) -> &'static str {
+        "pitch_shifter"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            self.line = PitchShifter::delay_line(sample_rate);
+            self.psola = Psola::new(sample_rate);
+            self.primed = false;
+        }
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        if self.primed {
+            self.ratio_s.set_target(self.ratio());
+            self.mix_s.set_target(self.mix);
+        } else {
+            self.ratio_s.snap(self.ratio());
+            self.mix_s.snap(self.mix);
+            self.primed = true;
+        }
+        let window = self.window_samples();
+        for sample in buffer.iter_mut() {
+            let x = *sample;
+            let ratio = self.ratio_s.tick();
+            let wet = if self.formant { self.psola.process(x, ratio) } else { self.shift_delay(x, ratio, window) };
+            let (wet_gain, dry_gain) = (self.mix_s.tick() * FRAC_PI_2).sin_cos();
+            *sample = x * dry_gain + wet * wet_gain;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.line.reset();
+        self.sweep = 0.0;
+        self.psola.reset();
+        self.primed = false;
+    }
+
+    fn latency(&self) -> usize {
+        if self.formant {
+            self.psola.latency()
+        } else {
+            (0.5 * self.window_samples()).round() as usize + 1
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "semitones" => self.set_semitones(value),
+            "cents" => self.set_cents(value),
+            "formant" => self.set_formant(param_bool(value)),
+            "window" => self.set_window(value),
+            "mix" => self.set_mix(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "semitones" => Some(self.semitones),
+            "cents" => Some(self.cents),
+            "formant" => Some(if self.formant { 1.0 } else { 0.0 }),
+            "window" => Some(self.window_ms),
+            "mix" => Some(self.mix),
+            "latency" => Some(self.latency() as f32),
+            "detected_pitch" => Some(self.detected_pitch()),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.ratio_s.configure(mode, time_ms, self.sample_rate);
+        self.mix_s.configure(mode, time_ms, self.sample_rate);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
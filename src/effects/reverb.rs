@@ -0,0 +1,346 @@
+use std::any::Any;
+
+use super::Effect;
+use crate::delay_line::{DelayInterpolation, DelayLine};
+use crate::params::UNIT_RANGE;
+use crate::smoothing::{SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+// Valid ranges - setters clamp into these and ignore NaN/inf
+pub const REVERB_DECAY_RANGE: (f32, f32) = (0.1, 20.0);
+pub const REVERB_PRE_DELAY_RANGE: (f32, f32) = (0.0, 0.25);
+
+const FDN_SIZE: usize = 8;
+
+// Feedback line lengths at full room size (ms), mutually prime-ish so the
+// echoes never line up
+const LINE_MS: [f32; FDN_SIZE] = [31.7, 37.1, 41.9, 47.3, 53.9, 59.3, 67.1, 73.7];
+
+// Input diffuser lengths (ms)
+const DIFFUSER_MS: [f32; 4] = [4.7, 3.6, 12.7, 9.3];
+
+// Smallest room is this fraction of the full line lengths
+const MIN_ROOM_SCALE: f32 = 0.25;
+
+// Loop low-pass coefficient at full damping (1.0 would freeze the loop)
+const MAX_DAMPING: f32 = 0.85;
+
+// Schroeder allpass with a fixed delay
+#[derive(Clone, Debug)]
+struct Diffuser {
+    buffer: Vec<f32>,
+    pos: usize,
+}
+
+impl Diffuser {
+    fn new(length: usize) -> Diffuser {
+        Diffuser {
+            buffer: vec![0.0; length.max(1)],
+            pos: 0,
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, x: f32, g: f32) -> f32 {
+        let delayed = self.buffer[self.pos];
+        let v = x - g * delayed;
+        self.buffer[self.pos] = v;
+        self.pos = (self.pos + 1) % self.buffer.len();
+        delayed + g * v
+    }
+
+    fn reset(&mut self) {
+        for s in self.buffer.iter_mut() {
+            *s = 0.0;
+        }
+        self.pos = 0;
+    }
+}
+
+// In-place 8-point fast Walsh-Hadamard transform, scaled to stay lossless
+#[inline]
+fn hadamard(x: &mut [f32; FDN_SIZE]) {
+    let mut h = 1;
+    while h < FDN_SIZE {
+        for i in (0..FDN_SIZE).step_by(h * 2) {
+            for j in i..i + h {
+                let (a, b) = (x[j], x[j + h]);
+                x[j] = a + b;
+                x[j + h] = a - b;
+            }
+        }
+        h *= 2;
+    }
+    let scale = 1.0 / (FDN_SIZE as f32).sqrt();
+    for v in x.iter_mut() {
+        *v *= scale;
+    }
+}
+
+// Eight-line feedback delay network reverb with diffused input
+// Each line loses exactly enough per pass to reach -60 dB after `decay`
+// seconds; a one-pole low-pass in every loop makes highs die first.
+// Params: "room_size" (0..1), "decay" (RT60 seconds), "damping" (0..1),
+//         "pre_delay" (seconds), "diffusion" (0..1), "mix" (0..1 equal-power)
+#[derive(Clone, Debug)]
+pub struct Reverb {
+    sample_rate: f32,
+    room_size: f32,
+    decay: f32,
+    damping: f32,
+    pre_delay: f32,
+    diffusion: f32,
+    mix: f32,
+    pre: DelayLine,
+    diffusers: Vec<Diffuser>,
+    lines: Vec<DelayLine>,
+    // Per-line loop gain and low-pass state
+    gains: [f32; FDN_SIZE],
+    lowpass: [f32; FDN_SIZE],
+    mix_s: SmoothedValue,
+    // Mix is fully off - skip the network entirely
+    idle: bool,
+    primed: bool,
+}
+
+impl Reverb {
+    pub fn new(sample_rate: f32) -> Reverb {
+        let mut reverb = Reverb {
+            sample_rate,
+            room_size: 0.5,
+            decay: 1.5,
+            damping: 0.5,
+            pre_delay: 0.02,
+            diffusion: 0.7,
+            mix: 0.0,
+            pre: DelayLine::new(1),
+            diffusers: Vec::new(),
+            lines: Vec::new(),
+            gains: [0.0; FDN_SIZE],
+            lowpass: [0.0; FDN_SIZE],
+            mix_s: SmoothedValue::new(0.0),
+            idle: true,
+            primed: false,
+        };
+        reverb.allocate();
+        reverb
+    }
+
+    pub fn set_room_size(&mut self, size: f32) {
+        if size.is_finite() {
+            self.room_size = size.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    // RT60 in seconds
+    pub fn set_decay(&mut self, seconds: f32) {
+        if seconds.is_finite() {
+            self.decay = seconds.clamp(REVERB_DECAY_RANGE.0, REVERB_DECAY_RANGE.1);
+        }
+    }
+
+    pub fn set_damping(&mut self, damping: f32) {
+        if damping.is_finite() {
+            self.damping = damping.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    pub fn set_pre_delay(&mut self, seconds: f32) {
+        if seconds.is_finite() {
+            self.pre_delay = seconds.clamp(REVERB_PRE_DELAY_RANGE.0, REVERB_PRE_DELAY_RANGE.1);
+        }
+    }
+
+    pub fn set_diffusion(&mut self, diffusion: f32) {
+        if diffusion.is_finite() {
+            self.diffusion = diffusion.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        if mix.is_finite() {
+            self.mix = mix.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    // Buffers for the current sample rate, sized for the largest room
+    fn allocate(&mut self) {
+        let ms = |ms: f32| (ms * 0.001 * self.sample_rate).ceil() as usize;
+        self.pre = DelayLine::new(ms(REVERB_PRE_DELAY_RANGE.1 * 1000.0));
+        self.diffusers = DIFFUSER_MS.iter().map(|&d| Diffuser::new(ms(d))).collect();
+        self.lines = LINE_MS
+            .iter()
+            .map(|&d| {
+                let mut line = DelayLine::new(ms(d));
+                line.set_interpolation(DelayInterpolation::Linear);
+                line.set_glide(SmoothingMode::Linear, 100.0, self.sample_rate);
+                line
+            })
+            .collect();
+        self.lowpass = [0.0; FDN_SIZE];
+        self.primed = false;
+    }
+
+    fn update_targets(&mut self) {
+        let scale = MIN_ROOM_SCALE + (1.0 - MIN_ROOM_SCALE) * self.room_size;
+        for (i, line) in self.lines.iter_mut().enumerate() {
+            let samples = LINE_MS[i] * 0.001 * scale * self.sample_rate;
+            if self.primed {
+                line.set_delay(samples);
+            } else {
+                line.snap_delay(samples);
+            }
+            // -60 dB after `decay` seconds: g = 10^(-3 * t_line / rt60)
+            let seconds = samples / self.sample_rate;
+            self.gains[i] = 10f32.powf(-3.0 * seconds / self.decay);
+        }
+        let pre = self.pre_delay * self.sample_rate;
+        if self.primed {
+            self.pre.set_delay(pre);
+            self.mix_s.set_target(self.mix);
+        } else {
+            self.pre.snap_delay(pre);
+            self.mix_s.snap(self.mix);
+            self.primed = true;
+        }
+    }
+
+    // True when the effect is off and can skip the network
+    fn check_idle(&mut self) -> bool {
+        let off = self.mix <= 0.0 && !self.mix_s.is_smoothing() && self.mix_s.current() <= 0.0;
+        if off {
+            self.idle = true;
+        } else if self.idle {
+            // Coming back on: start from an empty room, not a stale tail
+            self.clear();
+            self.idle = false;
+        }
+        off
+    }
+
+    #[inline]
+    fn tick(&mut self, x_left: f32, x_right: f32) -> (f32, f32) {
+        let mix = self.mix_s.tick();
+
+        let delayed = self.pre.read();
+        self.pre.write(0.5 * (x_left + x_right));
+
+        let g = 0.75 * self.diffusion;
+        let mut input = delayed;
+        for diffuser in self.diffusers.iter_mut() {
+            input = diffuser.process(input, g);
+        }
+
+        let mut taps = [0.0; FDN_SIZE];
+        let damping = self.damping * MAX_DAMPING;
+        for (i, line) in self.lines.iter_mut().enumerate() {
+            let y = line.read();
+            self.lowpass[i] = y + (self.lowpass[i] - y) * damping;
+            taps[i] = y;
+        }
+
+        let mut feedback: [f32; FDN_SIZE] = std::array::from_fn(|i| self.lowpass[i] * self.gains[i]);
+        hadamard(&mut feedback);
+        for (i, line) in self.lines.iter_mut().enumerate() {
+            // Alternate input polarity to decorrelate the lines
+            let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+            line.write(feedback[i] + sign * input);
+        }
+
+        // Left and right listen to different lines
+        let wet_left = 0.5 * (taps[0] - taps[2] + taps[4] - taps[6]);
+        let wet_right = 0.5 * (taps[1] - taps[3] + taps[5] - taps[7]);
+
+        // Equal-power mix
+        let (wet, dry) = (mix * std::f32::consts::FRAC_PI_2).sin_cos();
+        (x_left * dry + wet_left * wet, x_right * dry + wet_right * wet)
+    }
+
+    fn clear(&mut self) {
+        self.pre.reset();
+        for d in self.diffusers.iter_mut() {
+            d.reset();
+        }
+        for line in self.lines.iter_mut() {
+            line.reset();
+        }
+        self.lowpass = [0.0; FDN_SIZE];
+    }
+}
+
+impl Effect for Reverb {
+    fn name(&self) -> &'static str {
+        "reverb"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            self.allocate();
+        }
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn is_stereo(&self) -> bool {
+        true
+    }
+
+    // Mono fallback: both outputs folded down
+    fn process(&mut self, buffer: &mut [f32]) {
+        self.update_targets();
+        if self.check_idle() {
+            return;
+        }
+        for sample in buffer.iter_mut() {
+            let (l, r) = self.tick(*sample, *sample);
+            *sample = 0.5 * (l + r);
+        }
+    }
+
+    fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        self.update_targets();
+        if self.check_idle() {
+            return;
+        }
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            (*l, *r) = self.tick(*l, *r);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.clear();
+        self.primed = false;
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "room_size" => self.set_room_size(value),
+            "decay" => self.set_decay(value),
+            "damping" => self.set_damping(value),
+            "pre_delay" => self.set_pre_delay(value),
+            "diffusion" => self.set_diffusion(value),
+            "mix" => self.set_mix(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "room_size" => Some(self.room_size),
+            "decay" => Some(self.decay),
+            "damping" => Some(self.damping),
+            "pre_delay" => Some(self.pre_delay),
+            "diffusion" => Some(self.diffusion),
+            "mix" => Some(self.mix),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.mix_s.configure(mode, time_ms, self.sample_rate);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
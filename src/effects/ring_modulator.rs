@@ -0,0 +1,125 @@
+use std::any::Any;
+use std::f32::consts::FRAC_PI_2;
+
+use super::{param_index, Effect};
+use crate::lfo::{waveform, LfoShape};
+use crate::params::UNIT_RANGE;
+use crate::smoothing::{SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+// Carrier frequency in Hz
+pub const RING_FREQUENCY_RANGE: (f32, f32) = (1.0, 5000.0);
+
+// Ring modulator
+//
+// Multiplies the input by an audio-rate carrier, replacing each input
+// frequency with the sum and difference against the carrier - the classic
+// robot/Dalek voice at a few tens of Hz, metallic clangs higher up.
+// Params: "frequency" (carrier Hz), "shape" (carrier LfoShape index),
+//         "mix" (0..1 equal-power)
+#[derive(Clone, Debug)]
+pub struct RingModulator {
+    sample_rate: f32,
+    frequency: f32,
+    shape: LfoShape,
+    mix: f32,
+    phase: f64,
+    frequency_s: SmoothedValue,
+    mix_s: SmoothedValue,
+    primed: bool,
+}
+
+impl RingModulator {
+    pub fn new(sample_rate: f32) -> RingModulator {
+        RingModulator {
+            sample_rate,
+            frequency: 30.0,
+            shape: LfoShape::Sine,
+            mix: 1.0,
+            phase: 0.0,
+            frequency_s: SmoothedValue::new(0.0),
+            mix_s: SmoothedValue::new(0.0),
+            primed: false,
+        }
+    }
+
+    pub fn set_frequency(&mut self, hz: f32) {
+        if hz.is_finite() {
+            self.frequency = hz.clamp(RING_FREQUENCY_RANGE.0, RING_FREQUENCY_RANGE.1);
+        }
+    }
+
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.shape = shape;
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        if mix.is_finite() {
+            self.mix = mix.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+}
+
+impl Effect for RingModulator {
+    fn name(&self) -> &'static str {
+        "ring_modulator"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.sample_rate = sample_rate;
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        if self.primed {
+            self.frequency_s.set_target(self.frequency);
+            self.mix_s.set_target(self.mix);
+        } else {
+            self.frequency_s.snap(self.frequency);
+            self.mix_s.snap(self.mix);
+            self.primed = true;
+        }
+        let step = 1.0 / self.sample_rate as f64;
+        for sample in buffer.iter_mut() {
+            let carrier = waveform(self.shape, self.phase as f32);
+            self.phase = (self.phase + self.frequency_s.tick() as f64 * step).fract();
+            let (wet_gain, dry_gain) = (self.mix_s.tick() * FRAC_PI_2).sin_cos();
+            *sample = *sample * dry_gain + *sample * carrier * wet_gain;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.phase = 0.0;
+        self.primed = false;
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "frequency" => self.set_frequency(value),
+            "shape" => match param_index(value).and_then(LfoShape::from_index) {
+                Some(shape) => self.set_shape(shape),
+                None => return false,
+            },
+            "mix" => self.set_mix(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "frequency" => Some(self.frequency),
+            "shape" => Some(self.shape as u32 as f32),
+            "mix" => Some(self.mix),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.frequency_s.configure(mode, time_ms, self.sample_rate);
+        self.mix_s.configure(mode, time_ms, self.sample_rate);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,316 @@
+use std::any::Any;
+
+use wasm_bindgen::prelude::*;
+
+use super::{param_bool, param_index, Effect};
+use crate::delay_line::{DelayInterpolation, DelayLine, DEFAULT_GLIDE_MS};
+use crate::params::{DELAY_TIME_RANGE, UNIT_RANGE};
+use crate::smoothing::{SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+// How the input reaches the two delay lines
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StereoDelayMode {
+    // Left feeds left, right feeds right; cross feedback blends the loops
+    Stereo,
+    // Mono sum enters the left line and every repeat swaps sides
+    PingPong,
+}
+
+impl StereoDelayMode {
+    pub fn from_index(index: usize) -> Option<StereoDelayMode> {
+        [StereoDelayMode::Stereo, StereoDelayMode::PingPong].get(index).copied()
+    }
+}
+
+// Two one-second delay lines with a 2x2 feedback matrix
+// Params: "time" (seconds, both sides), "time_left", "time_right",
+//         "feedback" (0..1 loop gain), "cross_feedback" (0..1, share of each
+//         repeat sent to the other side), "width" (0 mono .. 1 full stereo echoes),
+//         "mix" (0..1 equal-power), "mode" (StereoDelayMode index),
+//         "safe_mode" (0/1), "glide" (ms), "interpolation" (DelayInterpolation index)
+#[derive(Clone, Debug)]
+pub struct StereoDelay {
+    sample_rate: f32,
+    mode: StereoDelayMode,
+    time_left: f32,
+    time_right: f32,
+    feedback: f32,
+    cross_feedback: f32,
+    width: f32,
+    mix: f32,
+    safe_mode: bool,
+    glide_ms: f32,
+    left: DelayLine,
+    right: DelayLine,
+    feedback_s: SmoothedValue,
+    cross_s: SmoothedValue,
+    width_s: SmoothedValue,
+    mix_s: SmoothedValue,
+    primed: bool,
+}
+
+impl StereoDelay {
+    pub fn new(sample_rate: f32) -> StereoDelay {
+        let mut delay = StereoDelay {
+            sample_rate,
+            mode: StereoDelayMode::Stereo,
+            time_left: 0.0,
+            time_right: 0.0,
+            feedback: 0.0,
+            cross_feedback: 0.0,
+            width: 1.0,
+            mix: 0.0,
+            safe_mode: true,
+            glide_ms: DEFAULT_GLIDE_MS,
+            left: DelayLine::new(sample_rate as usize),
+            right: DelayLine::new(sample_rate as usize),
+            feedback_s: SmoothedValue::new(0.0),
+            cross_s: SmoothedValue::new(0.0),
+            width_s: SmoothedValue::new(1.0),
+            mix_s: SmoothedValue::new(0.0),
+            primed: false,
+        };
+        delay.set_glide(DEFAULT_GLIDE_MS);
+        delay
+    }
+
+    pub fn set_mode(&mut self, mode: StereoDelayMode) {
+        self.mode = mode;
+    }
+
+    pub fn mode(&self) -> StereoDelayMode {
+        self.mode
+    }
+
+    // Same delay time on both sides
+    pub fn set_time(&mut self, seconds: f32) {
+        self.set_time_left(seconds);
+        self.set_time_right(seconds);
+    }
+
+    pub fn set_time_left(&mut self, seconds: f32) {
+        if seconds.is_finite() {
+            self.time_left = seconds.clamp(DELAY_TIME_RANGE.0, DELAY_TIME_RANGE.1);
+        }
+    }
+
+    pub fn set_time_right(&mut self, seconds: f32) {
+        if seconds.is_finite() {
+            self.time_right = seconds.clamp(DELAY_TIME_RANGE.0, DELAY_TIME_RANGE.1);
+        }
+    }
+
+    pub fn set_feedback(&mut self, feedback: f32) {
+        if feedback.is_finite() {
+            self.feedback = feedback.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    pub fn set_cross_feedback(&mut self, cross: f32) {
+        if cross.is_finite() {
+            self.cross_feedback = cross.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    pub fn set_width(&mut self, width: f32) {
+        if width.is_finite() {
+            self.width = width.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        if mix.is_finite() {
+            self.mix = mix.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    pub fn set_safe_mode(&mut self, safe_mode: bool) {
+        self.safe_mode = safe_mode;
+    }
+
+    // How long the read heads take to reach a new delay time (milliseconds)
+    pub fn set_glide(&mut self, time_ms: f32) {
+        self.glide_ms = if time_ms.is_finite() { time_ms.max(0.0) } else { DEFAULT_GLIDE_MS };
+        self.left.set_glide(SmoothingMode::Linear, self.glide_ms, self.sample_rate);
+        self.right.set_glide(SmoothingMode::Linear, self.glide_ms, self.sample_rate);
+    }
+
+    pub fn set_interpolation(&mut self, interpolation: DelayInterpolation) {
+        self.left.set_interpolation(interpolation);
+        self.right.set_interpolation(interpolation);
+    }
+
+    // Delay buffer length in samples, both sides (for memory monitoring)
+    pub fn buffer_len(&self) -> usize {
+        self.left.len() + self.right.len()
+    }
+
+    pub fn buffer_capacity(&self) -> usize {
+        self.left.capacity() + self.right.capacity()
+    }
+
+    fn update_targets(&mut self) {
+        let left_samples = self.time_left * self.sample_rate;
+        let right_samples = self.time_right * self.sample_rate;
+        // Fade the echoes out rather than cutting them when both times go to zero
+        let mix = if self.time_left.max(self.time_right) > 0.001 { self.mix } else { 0.0 };
+        // Ping-pong is a full swap every repeat
+        let cross = match self.mode {
+            StereoDelayMode::Stereo => self.cross_feedback,
+            StereoDelayMode::PingPong => 1.0,
+        };
+        if self.primed {
+            self.left.set_delay(left_samples);
+            self.right.set_delay(right_samples);
+            self.feedback_s.set_target(self.feedback);
+            self.cross_s.set_target(cross);
+            self.width_s.set_target(self.width);
+            self.mix_s.set_target(mix);
+        } else {
+            self.left.snap_delay(left_samples);
+            self.right.snap_delay(right_samples);
+            self.feedback_s.snap(self.feedback);
+            self.cross_s.snap(cross);
+            self.width_s.snap(self.width);
+            self.mix_s.snap(mix);
+            self.primed = true;
+        }
+    }
+
+    #[inline]
+    fn tick(&mut self, x_left: f32, x_right: f32) -> (f32, f32) {
+        let feedback = self.feedback_s.tick();
+        let cross = self.cross_s.tick();
+        let width = self.width_s.tick();
+        let mix = self.mix_s.tick();
+
+        // The read heads always move so time changes glide
+        let delayed_left = self.left.read();
+        let delayed_right = self.right.read();
+        if mix <= 0.001 {
+            // No delay - write silence
+            self.left.write(0.0);
+            self.right.write(0.0);
+            return (x_left, x_right);
+        }
+
+        // Feedback matrix [[1 - c, c], [c, 1 - c]] scaled by the loop gain
+        let mut fb_left = feedback * ((1.0 - cross) * delayed_left + cross * delayed_right);
+        let mut fb_right = feedback * ((1.0 - cross) * delayed_right + cross * delayed_left);
+        if self.safe_mode {
+            fb_left = fb_left.tanh();
+            fb_right = fb_right.tanh();
+        }
+        let (in_left, in_right) = match self.mode {
+            StereoDelayMode::Stereo => (x_left, x_right),
+            StereoDelayMode::PingPong => (0.5 * (x_left + x_right), 0.0),
+        };
+        self.left.write(in_left + fb_left);
+        self.right.write(in_right + fb_right);
+
+        // Width narrows the echoes towards the centre (mid/side)
+        let mid = 0.5 * (delayed_left + delayed_right);
+        let side = 0.5 * (delayed_left - delayed_right) * width;
+
+        // Equal-power mix
+        let (wet, dry) = (mix * std::f32::consts::FRAC_PI_2).sin_cos();
+        (x_left * dry + (mid + side) * wet, x_right * dry + (mid - side) * wet)
+    }
+}
+
+impl Effect for StereoDelay {
+    fn name(&self) -> &'static str {
+        "stereo_delay"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            let interpolation = self.left.interpolation();
+            self.left = DelayLine::new(sample_rate as usize);
+            self.right = DelayLine::new(sample_rate as usize);
+            self.set_interpolation(interpolation);
+            self.set_glide(self.glide_ms);
+            self.primed = false;
+        }
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn is_stereo(&self) -> bool {
+        true
+    }
+
+    // Mono fallback: the same signal feeds both sides and the result is folded down
+    fn process(&mut self, buffer: &mut [f32]) {
+        self.update_targets();
+        for sample in buffer.iter_mut() {
+            let (l, r) = self.tick(*sample, *sample);
+            *sample = 0.5 * (l + r);
+        }
+    }
+
+    fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        self.update_targets();
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            (*l, *r) = self.tick(*l, *r);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.left.reset();
+        self.right.reset();
+        self.primed = false;
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "time" => self.set_time(value),
+            "time_left" => self.set_time_left(value),
+            "time_right" => self.set_time_right(value),
+            "feedback" => self.set_feedback(value),
+            "cross_feedback" => self.set_cross_feedback(value),
+            "width" => self.set_width(value),
+            "mix" => self.set_mix(value),
+            "mode" => match param_index(value).and_then(StereoDelayMode::from_index) {
+                Some(mode) => self.set_mode(mode),
+                None => return false,
+            },
+            "safe_mode" => self.set_safe_mode(param_bool(value)),
+            "glide" => self.set_glide(value),
+            "interpolation" => match param_index(value).and_then(DelayInterpolation::from_index) {
+                Some(interpolation) => self.set_interpolation(interpolation),
+                None => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "time" | "time_left" => Some(self.time_left),
+            "time_right" => Some(self.time_right),
+            "feedback" => Some(self.feedback),
+            "cross_feedback" => Some(self.cross_feedback),
+            "width" => Some(self.width),
+            "mix" => Some(self.mix),
+            "mode" => Some(self.mode as u32 as f32),
+            "safe_mode" => Some(if self.safe_mode { 1.0 } else { 0.0 }),
+            "glide" => Some(self.glide_ms),
+            "interpolation" => Some(self.left.interpolation() as u32 as f32),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.feedback_s.configure(mode, time_ms, self.sample_rate);
+        self.cross_s.configure(mode, time_ms, self.sample_rate);
+        self.width_s.configure(mode, time_ms, self.sample_rate);
+        self.mix_s.configure(mode, time_ms, self.sample_rate);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
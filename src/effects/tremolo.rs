@@ -0,0 +1,128 @@
+use std::any::Any;
+
+use super::{param_index, Effect};
+use crate::lfo::{Lfo, LfoShape};
+use crate::params::UNIT_RANGE;
+use crate::smoothing::{one_pole, SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+// Time constant that rounds off the LFO's corners, in ms
+pub const TREMOLO_SMOOTHING_RANGE: (f32, f32) = (0.0, 50.0);
+
+// Tremolo (amplitude modulation)
+//
+// The gain swings between 1 and 1 - depth with the LFO. The LFO runs
+// through a one-pole first so square and saw shapes switch with a short
+// ramp instead of clicking; 0 smoothing keeps the hard edges.
+// Params: "rate" (Hz), "depth" (0..1), "shape" (LfoShape index),
+//         "smoothing" (ms)
+#[derive(Clone, Debug)]
+pub struct Tremolo {
+    sample_rate: f32,
+    depth: f32,
+    smoothing_ms: f32,
+    lfo: Lfo,
+    // LFO after the corner smoothing
+    shaped: f32,
+    depth_s: SmoothedValue,
+    primed: bool,
+}
+
+impl Tremolo {
+    pub fn new(sample_rate: f32) -> Tremolo {
+        Tremolo {
+            sample_rate,
+            depth: 0.5,
+            smoothing_ms: 5.0,
+            lfo: Lfo::new(LfoShape::Sine, 5.0, sample_rate),
+            shaped: 0.0,
+            depth_s: SmoothedValue::new(0.0),
+            primed: false,
+        }
+    }
+
+    pub fn set_rate(&mut self, hz: f32) {
+        self.lfo.set_rate(hz);
+    }
+
+    pub fn set_depth(&mut self, depth: f32) {
+        if depth.is_finite() {
+            self.depth = depth.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.lfo.set_shape(shape);
+    }
+
+    pub fn set_smoothing_time(&mut self, time_ms: f32) {
+        if time_ms.is_finite() {
+            self.smoothing_ms = time_ms.clamp(TREMOLO_SMOOTHING_RANGE.0, TREMOLO_SMOOTHING_RANGE.1);
+        }
+    }
+}
+
+impl Effect for Tremolo {
+    fn name(&self) -> &'static str {
+        "tremolo"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.sample_rate = sample_rate;
+        self.lfo.set_sample_rate(sample_rate);
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        if self.primed {
+            self.depth_s.set_target(self.depth);
+        } else {
+            self.depth_s.snap(self.depth);
+            self.shaped = self.lfo.value_at(0.0);
+            self.primed = true;
+        }
+        let coeff = if self.smoothing_ms > 0.0 { one_pole(self.smoothing_ms, self.sample_rate) } else { 0.0 };
+        for sample in buffer.iter_mut() {
+            let lfo = self.lfo.tick();
+            self.shaped = lfo + (self.shaped - lfo) * coeff;
+            let depth = self.depth_s.tick();
+            *sample *= 1.0 - depth * 0.5 * (1.0 - self.shaped);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.lfo.reset();
+        self.primed = false;
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "rate" => self.set_rate(value),
+            "depth" => self.set_depth(value),
+            "shape" => match param_index(value).and_then(LfoShape::from_index) {
+                Some(shape) => self.set_shape(shape),
+                None => return false,
+            },
+            "smoothing" => self.set_smoothing_time(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "rate" => Some(self.lfo.rate()),
+            "depth" => Some(self.depth),
+            "shape" => Some(self.lfo.shape() as u32 as f32),
+            "smoothing" => Some(self.smoothing_ms),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.depth_s.configure(mode, time_ms, self.sample_rate);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
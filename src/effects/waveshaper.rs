@@ -0,0 +1,333 @@
+use std::any::Any;
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use wasm_bindgen::prelude::*;
+
+use super::{param_index, Effect};
+use crate::biquad::{Biquad, FilterType};
+use crate::params::{GAIN_RANGE, UNIT_RANGE};
+use crate::smoothing::{db_to_gain, SmoothedValue, SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+pub const DRIVE_DB_RANGE: (f32, f32) = (0.0, 48.0);
+// Tone filter cutoffs; 0 turns a filter off
+pub const PRE_FILTER_RANGE: (f32, f32) = (20.0, 2000.0);
+pub const POST_FILTER_RANGE: (f32, f32) = (1000.0, 20000.0);
+pub const MAX_TABLE_LEN: usize = 8192;
+
+// Offset that makes the tube curve asymmetric (even harmonics)
+const TUBE_BIAS: f32 = 0.3;
+// DC blocker corner for the asymmetric curves
+const DC_BLOCK_HZ: f32 = 5.0;
+
+// Transfer curves, applied to the driven signal
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaveshaperCurve {
+    // tanh - smooth, odd harmonics
+    SoftClip,
+    // Flat ceiling at +/-1
+    HardClip,
+    // Biased tanh - the two half-waves clip differently (even harmonics)
+    Tube,
+    // Reflects back from +/-1 instead of clipping
+    Foldback,
+    // arctan - softer knee than tanh
+    Arctan,
+    // 1.5x - 0.5x^3 up to +/-1, then flat
+    Cubic,
+    // sin(x * pi/2) - folds smoothly and keeps folding with drive
+    SineFold,
+    // User transfer table spanning -1..1 (see set_table)
+    Table,
+}
+
+impl WaveshaperCurve {
+    pub fn from_index(index: usize) -> Option<WaveshaperCurve> {
+        [
+            WaveshaperCurve::SoftClip,
+            WaveshaperCurve::HardClip,
+            WaveshaperCurve::Tube,
+            WaveshaperCurve::Foldback,
+            WaveshaperCurve::Arctan,
+            WaveshaperCurve::Cubic,
+            WaveshaperCurve::SineFold,
+            WaveshaperCurve::Table,
+        ]
+        .get(index)
+        .copied()
+    }
+
+    // Folding curves are not rescaled by the drive
+    fn saturates(self) -> bool {
+        !matches!(self, WaveshaperCurve::Foldback | WaveshaperCurve::SineFold | WaveshaperCurve::Table)
+    }
+}
+
+// The curve at x (the Table curve passes through here unchanged)
+#[inline]
+pub fn shape(curve: WaveshaperCurve, x: f32) -> f32 {
+    match curve {
+        WaveshaperCurve::SoftClip => x.tanh(),
+        WaveshaperCurve::HardClip => x.clamp(-1.0, 1.0),
+        WaveshaperCurve::Tube => (x + TUBE_BIAS).tanh() - TUBE_BIAS.tanh(),
+        WaveshaperCurve::Foldback => {
+            // Triangle wave through the origin with period 4
+            let t = (x + 1.0).rem_euclid(4.0);
+            if t < 2.0 {
+                t - 1.0
+            } else {
+                3.0 - t
+            }
+        }
+        WaveshaperCurve::Arctan => x.atan(),
+        WaveshaperCurve::Cubic => {
+            let x = x.clamp(-1.0, 1.0);
+            1.5 * x - 0.5 * x * x * x
+        }
+        WaveshaperCurve::SineFold => (x * FRAC_PI_2).sin(),
+        WaveshaperCurve::Table => x,
+    }
+}
+
+// Gain that brings a full-scale input back to full scale after `drive`
+pub fn normalisation(curve: WaveshaperCurve, drive: f32) -> f32 {
+    if !curve.saturates() {
+        return 1.0;
+    }
+    let peak = shape(curve, drive).abs().max(shape(curve, -drive).abs());
+    if peak > 1e-6 {
+        1.0 / peak
+    } else {
+        1.0
+    }
+}
+
+// Waveshaping distortion
+//
+// Signal path: pre high-pass -> drive -> curve -> DC blocker -> post
+// low-pass -> output gain, blended with the dry input. The pre filter
+// keeps low end from muddying the distortion, the post filter tames fizz.
+// Saturating curves are normalised so full scale stays at full scale.
+// Params: "curve" (WaveshaperCurve index), "drive" (dB), "pre_filter"
+//         (high-pass Hz, 0 = off), "post_filter" (low-pass Hz, 0 = off),
+//         "output" (linear gain), "mix" (0..1 equal-power)
+#[derive(Clone, Debug)]
+pub struct Waveshaper {
+    sample_rate: f32,
+    curve: WaveshaperCurve,
+    drive_db: f32,
+    pre_hz: f32,
+    post_hz: f32,
+    output: f32,
+    mix: f32,
+    // Transfer table for WaveshaperCurve::Table
+    table: Vec<f32>,
+    pre: Biquad,
+    post: Biquad,
+    dc_x1: f32,
+    dc_y1: f32,
+    drive_s: SmoothedValue,
+    output_s: SmoothedValue,
+    mix_s: SmoothedValue,
+    primed: bool,
+}
+
+impl Waveshaper {
+    pub fn new(sample_rate: f32) -> Waveshaper {
+        Waveshaper {
+            sample_rate,
+            curve: WaveshaperCurve::SoftClip,
+            drive_db: 12.0,
+            pre_hz: 0.0,
+            post_hz: 0.0,
+            output: 1.0,
+            mix: 1.0,
+            table: vec![-1.0, 1.0],
+            pre: Biquad::new(FilterType::HighPass, PRE_FILTER_RANGE.0, sample_rate),
+            post: Biquad::new(FilterType::LowPass, POST_FILTER_RANGE.1, sample_rate),
+            dc_x1: 0.0,
+            dc_y1: 0.0,
+            drive_s: SmoothedValue::new(1.0),
+            output_s: SmoothedValue::new(1.0),
+            mix_s: SmoothedValue::new(1.0),
+            primed: false,
+        }
+    }
+
+    pub fn set_curve(&mut self, curve: WaveshaperCurve) {
+        self.curve = curve;
+    }
+
+    pub fn curve(&self) -> WaveshaperCurve {
+        self.curve
+    }
+
+    pub fn set_drive(&mut self, db: f32) {
+        if db.is_finite() {
+            self.drive_db = db.clamp(DRIVE_DB_RANGE.0, DRIVE_DB_RANGE.1);
+        }
+    }
+
+    // High-pass before the curve, in Hz (0 = off)
+    pub fn set_pre_filter(&mut self, hz: f32) {
+        if hz.is_finite() {
+            self.pre_hz = if hz <= 0.0 { 0.0 } else { hz.clamp(PRE_FILTER_RANGE.0, PRE_FILTER_RANGE.1) };
+            if self.pre_hz > 0.0 {
+                self.pre.set_frequency(self.pre_hz);
+            }
+        }
+    }
+
+    // Low-pass after the curve, in Hz (0 = off)
+    pub fn set_post_filter(&mut self, hz: f32) {
+        if hz.is_finite() {
+            self.post_hz = if hz <= 0.0 { 0.0 } else { hz.clamp(POST_FILTER_RANGE.0, POST_FILTER_RANGE.1) };
+            if self.post_hz > 0.0 {
+                self.post.set_frequency(self.post_hz);
+            }
+        }
+    }
+
+    pub fn set_output(&mut self, gain: f32) {
+        if gain.is_finite() {
+            self.output = gain.clamp(GAIN_RANGE.0, GAIN_RANGE.1);
+        }
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        if mix.is_finite() {
+            self.mix = mix.clamp(UNIT_RANGE.0, UNIT_RANGE.1);
+        }
+    }
+
+    // Transfer table for WaveshaperCurve::Table: entries evenly cover the
+    // driven input from -1 to 1 and are linearly interpolated; input beyond
+    // that holds the end values. Rejects empty, oversized or non-finite tables.
+    pub fn set_table(&mut self, table: &[f32]) -> bool {
+        if table.is_empty() || table.len() > MAX_TABLE_LEN || table.iter().any(|x| !x.is_finite()) {
+            return false;
+        }
+        self.table.clear();
+        self.table.extend_from_slice(table);
+        if self.table.len() == 1 {
+            self.table.push(table[0]);
+        }
+        true
+    }
+
+    pub fn table(&self) -> &[f32] {
+        &self.table
+    }
+
+    #[inline]
+    fn lookup(&self, x: f32) -> f32 {
+        let last = self.table.len() - 1;
+        let pos = (x.clamp(-1.0, 1.0) + 1.0) * 0.5 * last as f32;
+        let i = (pos as usize).min(last - 1);
+        let frac = pos - i as f32;
+        self.table[i] + (self.table[i + 1] - self.table[i]) * frac
+    }
+}
+
+impl Effect for Waveshaper {
+    fn name(&self) -> &'static str {
+        "waveshaper"
+    }
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.sample_rate = sample_rate;
+        self.pre.set_sample_rate(sample_rate);
+        self.post.set_sample_rate(sample_rate);
+        self.pre.snap();
+        self.post.snap();
+        self.set_smoothing(SmoothingMode::Linear, DEFAULT_SMOOTHING_MS);
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        let drive = db_to_gain(self.drive_db);
+        if self.primed {
+            self.drive_s.set_target(drive);
+            self.output_s.set_target(self.output);
+            self.mix_s.set_target(self.mix);
+        } else {
+            self.drive_s.snap(drive);
+            self.output_s.snap(self.output);
+            self.mix_s.snap(self.mix);
+            self.primed = true;
+        }
+        self.pre.update();
+        self.post.update();
+
+        let curve = self.curve;
+        let dc_coeff = 1.0 - 2.0 * PI * DC_BLOCK_HZ / self.sample_rate;
+        for sample in buffer.iter_mut() {
+            let dry = *sample;
+            let drive = self.drive_s.tick();
+            let output = self.output_s.tick();
+            let mix = self.mix_s.tick();
+
+            let mut x = if self.pre_hz > 0.0 { self.pre.process_sample(dry) } else { dry };
+            x = if curve == WaveshaperCurve::Table {
+                self.lookup(x * drive)
+            } else {
+                shape(curve, x * drive) * normalisation(curve, drive)
+            };
+            // DC blocker - asymmetric curves and tables leave an offset
+            let y = x - self.dc_x1 + dc_coeff * self.dc_y1;
+            self.dc_x1 = x;
+            self.dc_y1 = y;
+            let wet = if self.post_hz > 0.0 { self.post.process_sample(y) } else { y };
+
+            let (wet_gain, dry_gain) = (mix * FRAC_PI_2).sin_cos();
+            *sample = dry * dry_gain + wet * output * wet_gain;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.pre.reset();
+        self.post.reset();
+        self.dc_x1 = 0.0;
+        self.dc_y1 = 0.0;
+        self.primed = false;
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "curve" => match param_index(value).and_then(WaveshaperCurve::from_index) {
+                Some(curve) => self.set_curve(curve),
+                None => return false,
+            },
+            "drive" => self.set_drive(value),
+            "pre_filter" => self.set_pre_filter(value),
+            "post_filter" => self.set_post_filter(value),
+            "output" => self.set_output(value),
+            "mix" => self.set_mix(value),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "curve" => Some(self.curve as u32 as f32),
+            "drive" => Some(self.drive_db),
+            "pre_filter" => Some(self.pre_hz),
+            "post_filter" => Some(self.post_hz),
+            "output" => Some(self.output),
+            "mix" => Some(self.mix),
+            _ => None,
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.drive_s.configure(mode, time_ms, self.sample_rate);
+        self.output_s.configure(mode, time_ms, self.sample_rate);
+        self.mix_s.configure(mode, time_ms, self.sample_rate);
+        self.pre.set_smoothing(mode, time_ms);
+        self.post.set_smoothing(mode, time_ms);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,460 @@
+use std::f32::consts::PI;
+use std::ops::{Add, Mul, Sub};
+
+use wasm_bindgen::prelude::*;
+
+use crate::window::{coherent_gain, WindowType};
+
+// Complex sample for the FFT
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub const ZERO: Complex = Complex { re: 0.0, im: 0.0 };
+
+    pub fn new(re: f32, im: f32) -> Complex {
+        Complex { re, im }
+    }
+
+    // e^(i * angle)
+    pub fn from_angle(angle: f32) -> Complex {
+        let (im, re) = angle.sin_cos();
+        Complex { re, im }
+    }
+
+    pub fn conj(self) -> Complex {
+        Complex::new(self.re, -self.im)
+    }
+
+    pub fn norm_sqr(self) -> f32 {
+        self.re * self.re + self.im * self.im
+    }
+
+    pub fn abs(self) -> f32 {
+        self.norm_sqr().sqrt()
+    }
+
+    pub fn scale(self, k: f32) -> Complex {
+        Complex::new(self.re * k, self.im * k)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, o: Complex) -> Complex {
+        Complex::new(self.re + o.re, self.im + o.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, o: Complex) -> Complex {
+        Complex::new(self.re - o.re, self.im - o.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, o: Complex) -> Complex {
+        Complex::new(self.re * o.re - self.im * o.im, self.re * o.im + self.im * o.re)
+    }
+}
+
+// Complex FFT of a fixed size, planned once so transforms never allocate
+// Powers of two use an in-place iterative radix-2 transform; any other
+// size is split into prime factors (mixed radix, decimation in time)
+#[derive(Clone, Debug)]
+pub struct Fft {
+    size: usize,
+    // e^(-2*pi*i*k/N) for k in 0..N
+    twiddles: Vec<Complex>,
+    // Radix-2 only
+    bit_reverse: Vec<usize>,
+    // Mixed radix only: (radix, remaining length) per stage
+    factors: Vec<(usize, usize)>,
+    scratch: Vec<Complex>,
+    butterfly: Vec<Complex>,
+}
+
+impl Fft {
+    pub fn new(size: usize) -> Fft {
+        let size = size.max(1);
+        let twiddles = (0..size)
+            .map(|k| Complex::from_angle(-2.0 * PI * k as f32 / size as f32))
+            .collect();
+        let mut fft = Fft {
+            size,
+            twiddles,
+            bit_reverse: Vec::new(),
+            factors: Vec::new(),
+            scratch: Vec::new(),
+            butterfly: Vec::new(),
+        };
+        if size.is_power_of_two() {
+            let bits = size.trailing_zeros();
+            fft.bit_reverse = (0..size)
+                .map(|i| if bits == 0 { 0 } else { i.reverse_bits() >> (usize::BITS - bits) })
+                .collect();
+        } else {
+            fft.factors = factorize(size);
+            let largest = fft.factors.iter().map(|&(p, _)| p).max().unwrap_or(1);
+            fft.scratch = vec![Complex::ZERO; size];
+            fft.butterfly = vec![Complex::ZERO; largest];
+        }
+        fft
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_radix2(&self) -> bool {
+        self.factors.is_empty()
+    }
+
+    // Forward transform (no scaling)
+    pub fn forward(&mut self, data: &mut [Complex]) {
+        if self.is_radix2() {
+            self.radix2(data, false);
+        } else {
+            self.mixed_radix(data);
+        }
+    }
+
+    // Inverse transform, scaled by 1/size so forward + inverse is identity
+    pub fn inverse(&mut self, data: &mut [Complex]) {
+        let scale = 1.0 / self.size as f32;
+        if self.is_radix2() {
+            self.radix2(data, true);
+            for x in data[..self.size].iter_mut() {
+                *x = x.scale(scale);
+            }
+        } else {
+            // ifft(x) = conj(fft(conj(x))) / N
+            for x in data[..self.size].iter_mut() {
+                *x = x.conj();
+            }
+            self.mixed_radix(data);
+            for x in data[..self.size].iter_mut() {
+                *x = x.conj().scale(scale);
+            }
+        }
+    }
+
+    fn radix2(&self, data: &mut [Complex], inverse: bool) {
+        let n = self.size;
+        let data = &mut data[..n];
+        for (i, &j) in self.bit_reverse.iter().enumerate() {
+            if i < j {
+                data.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= n {
+            let half = len / 2;
+            let step = n / len;
+            for start in (0..n).step_by(len) {
+                for k in 0..half {
+                    let w = self.twiddles[k * step];
+                    let w = if inverse { w.conj() } else { w };
+                    let a = data[start + k];
+                    let b = data[start + k + half] * w;
+                    data[start + k] = a + b;
+                    data[start + k + half] = a - b;
+                }
+            }
+            len *= 2;
+        }
+    }
+
+    fn mixed_radix(&mut self, data: &mut [Complex]) {
+        let n = self.size;
+        self.scratch.copy_from_slice(&data[..n]);
+        let mut butterfly = std::mem::take(&mut self.butterfly);
+        self.work(&mut data[..n], 0, 1, 0, &mut butterfly);
+        self.butterfly = butterfly;
+    }
+
+    // Recursive decimation in time: `out` receives the transform of the
+    // scratch samples starting at `offset` with spacing `stride`
+    fn work(&self, out: &mut [Complex], offset: usize, stride: usize, stage: usize, butterfly: &mut [Complex]) {
+        let (p, m) = self.factors[stage];
+        if m == 1 {
+            for (i, x) in out.iter_mut().enumerate().take(p) {
+                *x = self.scratch[offset + i * stride];
+            }
+        } else {
+            for (i, chunk) in out.chunks_mut(m).enumerate().take(p) {
+                self.work(chunk, offset + i * stride, stride * p, stage + 1, butterfly);
+            }
+        }
+
+        // Radix-p butterflies combining p sub-transforms of length m
+        let n = self.size;
+        for u in 0..m {
+            for (q, b) in butterfly.iter_mut().enumerate().take(p) {
+                *b = out[u + q * m];
+            }
+            for q1 in 0..p {
+                let k = u + q1 * m;
+                let mut sum = butterfly[0];
+                let mut twiddle = 0;
+                for &b in butterfly[1..p].iter() {
+                    twiddle = (twiddle + stride * k) % n;
+                    sum = sum + b * self.twiddles[twiddle];
+                }
+                out[k] = sum;
+            }
+        }
+    }
+}
+
+// Prime factors of n as (radix, length still to split) stages
+fn factorize(n: usize) -> Vec<(usize, usize)> {
+    let mut factors = Vec::new();
+    let mut remaining = n;
+    let mut p = 2;
+    while remaining > 1 {
+        while !remaining.is_multiple_of(p) {
+            p = if p == 2 { 3 } else { p + 2 };
+            if p * p > remaining {
+                p = remaining;
+            }
+        }
+        remaining /= p;
+        factors.push((p, remaining));
+    }
+    factors
+}
+
+// Real-input FFT of any even size N using one complex FFT of size N/2
+// The spectrum holds the N/2 + 1 non-negative frequency bins
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct RealFft {
+    size: usize,
+    half: Fft,
+    // e^(-2*pi*i*k/N) for splitting the packed half-size transform
+    twiddles: Vec<Complex>,
+    scratch: Vec<Complex>,
+}
+
+#[wasm_bindgen]
+impl RealFft {
+    // `size` must be even; odd sizes are rounded up
+    #[wasm_bindgen(constructor)]
+    pub fn new(size: usize) -> RealFft {
+        let size = size.max(2).next_multiple_of(2);
+        let half = size / 2;
+        RealFft {
+            size,
+            half: Fft::new(half),
+            twiddles: (0..half)
+                .map(|k| Complex::from_angle(-2.0 * PI * k as f32 / size as f32))
+                .collect(),
+            scratch: vec![Complex::ZERO; half],
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // Number of bins in the spectrum (size / 2 + 1)
+    pub fn bins(&self) -> usize {
+        self.size / 2 + 1
+    }
+
+    // Spectrum as interleaved [re0, im0, re1, im1, ...] (size / 2 + 1 bins)
+    // Shorter input is zero-padded, longer input truncated
+    pub fn forward_interleaved(&mut self, input: &[f32]) -> Vec<f32> {
+        let spectrum = self.spectrum_of(input, None);
+        spectrum.iter().flat_map(|c| [c.re, c.im]).collect()
+    }
+
+    // Inverse of forward_interleaved() - returns size samples
+    pub fn inverse_interleaved(&mut self, spectrum: &[f32]) -> Vec<f32> {
+        let bins: Vec<Complex> = (0..self.bins())
+            .map(|k| {
+                let re = spectrum.get(2 * k).copied().unwrap_or(0.0);
+                let im = spectrum.get(2 * k + 1).copied().unwrap_or(0.0);
+                Complex::new(re, im)
+            })
+            .collect();
+        let mut output = vec![0.0; self.size];
+        self.inverse(&bins, &mut output);
+        output
+    }
+
+    // Windowed magnitude per bin, scaled so a full-scale sine reads 1.0
+    pub fn magnitudes(&mut self, input: &[f32], window: WindowType) -> Vec<f32> {
+        let scale = 2.0 / (self.size as f32 * coherent_gain(window));
+        self.spectrum_of(input, Some(window))
+            .iter()
+            .map(|c| c.abs() * scale)
+            .collect()
+    }
+
+    // magnitudes() in dBFS, floored at -200 dB
+    pub fn magnitudes_db(&mut self, input: &[f32], window: WindowType) -> Vec<f32> {
+        self.magnitudes(input, window)
+            .iter()
+            .map(|&m| 20.0 * m.max(1e-10).log10())
+            .collect()
+    }
+}
+
+impl RealFft {
+    fn spectrum_of(&mut self, input: &[f32], window: Option<WindowType>) -> Vec<Complex> {
+        let n = self.size;
+        let mut frame: Vec<f32> = (0..n).map(|i| input.get(i).copied().unwrap_or(0.0)).collect();
+        if let Some(window) = window {
+            for (i, x) in frame.iter_mut().enumerate() {
+                *x *= window.value(i, n);
+            }
+        }
+        let mut spectrum = vec![Complex::ZERO; self.bins()];
+        self.forward(&frame, &mut spectrum);
+        spectrum
+    }
+
+    // `input` holds size samples, `spectrum` size / 2 + 1 bins
+    pub fn forward(&mut self, input: &[f32], spectrum: &mut [Complex]) {
+        let half = self.size / 2;
+        for (k, z) in self.scratch.iter_mut().enumerate() {
+            *z = Complex::new(input[2 * k], input[2 * k + 1]);
+        }
+        self.half.forward(&mut self.scratch);
+
+        let z0 = self.scratch[0];
+        spectrum[0] = Complex::new(z0.re + z0.im, 0.0);
+        spectrum[half] = Complex::new(z0.re - z0.im, 0.0);
+        for (k, bin) in spectrum.iter_mut().enumerate().take(half).skip(1) {
+            let a = self.scratch[k];
+            let b = self.scratch[half - k].conj();
+            let even = (a + b).scale(0.5);
+            let odd = (a - b) * Complex::new(0.0, -0.5);
+            *bin = even + self.twiddles[k] * odd;
+        }
+    }
+
+    // Inverse of forward(), scaled so the round trip is identity
+    pub fn inverse(&mut self, spectrum: &[Complex], output: &mut [f32]) {
+        let half = self.size / 2;
+        for (k, z) in self.scratch.iter_mut().enumerate() {
+            let a = spectrum[k];
+            let b = spectrum[half - k].conj();
+            let even = a + b;
+            let odd = (a - b) * self.twiddles[k].conj();
+            *z = (even + odd * Complex::new(0.0, 1.0)).scale(0.5);
+        }
+        self.half.inverse(&mut self.scratch);
+        for (k, z) in self.scratch.iter().enumerate() {
+            output[2 * k] = z.re;
+            output[2 * k + 1] = z.im;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Deterministic test signal in -1..1
+    fn signal(n: usize, seed: u32) -> Vec<f32> {
+        let mut state = seed;
+        (0..n)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                (state >> 8) as f32 / (1 << 23) as f32 - 1.0
+            })
+            .collect()
+    }
+
+    fn complex_signal(n: usize) -> Vec<Complex> {
+        let re = signal(n, 1);
+        let im = signal(n, 2);
+        re.into_iter().zip(im).map(|(re, im)| Complex::new(re, im)).collect()
+    }
+
+    // Textbook O(n^2) DFT in f64
+    fn naive_dft(input: &[Complex]) -> Vec<Complex> {
+        let n = input.len();
+        (0..n)
+            .map(|k| {
+                let (mut re, mut im) = (0.0f64, 0.0f64);
+                for (t, x) in input.iter().enumerate() {
+                    let angle = -2.0 * std::f64::consts::PI * ((k * t) % n) as f64 / n as f64;
+                    let (s, c) = angle.sin_cos();
+                    re += x.re as f64 * c - x.im as f64 * s;
+                    im += x.re as f64 * s + x.im as f64 * c;
+                }
+                Complex::new(re as f32, im as f32)
+            })
+            .collect()
+    }
+
+    fn assert_close(actual: &[Complex], expected: &[Complex], tolerance: f32, what: &str) {
+        assert_eq!(actual.len(), expected.len(), "{what}: length");
+        for (k, (a, e)) in actual.iter().zip(expected).enumerate() {
+            let error = (*a - *e).abs();
+            assert!(error <= tolerance, "{what}: bin {k} off by {error} ({a:?} vs {e:?})");
+        }
+    }
+
+    // Powers of two, primes and mixed factorisations
+    const SIZES: [usize; 12] = [1, 2, 3, 5, 7, 8, 49, 64, 97, 120, 1000, 1024];
+
+    #[test]
+    fn forward_matches_naive_dft() {
+        for n in SIZES {
+            let input = complex_signal(n);
+            let mut data = input.clone();
+            Fft::new(n).forward(&mut data);
+            assert_close(&data, &naive_dft(&input), 1e-5 * n as f32, &format!("n = {n}"));
+        }
+    }
+
+    #[test]
+    fn inverse_round_trips() {
+        for n in SIZES {
+            let input = complex_signal(n);
+            let mut data = input.clone();
+            let mut fft = Fft::new(n);
+            fft.forward(&mut data);
+            fft.inverse(&mut data);
+            assert_close(&data, &input, 1e-5, &format!("n = {n}"));
+        }
+    }
+
+    #[test]
+    fn real_forward_matches_naive_dft() {
+        for n in [2, 6, 10, 14, 98, 240, 1000, 1024] {
+            let input = signal(n, 3);
+            let mut spectrum = vec![Complex::ZERO; n / 2 + 1];
+            RealFft::new(n).forward(&input, &mut spectrum);
+            let complex: Vec<Complex> = input.iter().map(|&x| Complex::new(x, 0.0)).collect();
+            let expected = naive_dft(&complex);
+            assert_close(&spectrum, &expected[..n / 2 + 1], 1e-5 * n as f32, &format!("n = {n}"));
+        }
+    }
+
+    #[test]
+    fn real_inverse_round_trips() {
+        for n in [2, 6, 10, 14, 98, 240, 1000, 1024] {
+            let input = signal(n, 4);
+            let mut fft = RealFft::new(n);
+            let mut spectrum = vec![Complex::ZERO; fft.bins()];
+            let mut output = vec![0.0; n];
+            fft.forward(&input, &mut spectrum);
+            fft.inverse(&spectrum, &mut output);
+            for (i, (y, x)) in output.iter().zip(&input).enumerate() {
+                assert!((y - x).abs() <= 1e-5, "n = {n}: sample {i} is {y}, expected {x}");
+            }
+        }
+    }
+}
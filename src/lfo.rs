@@ -0,0 +1,141 @@
+use std::f32::consts::TAU;
+
+use wasm_bindgen::prelude::*;
+
+pub const LFO_RATE_RANGE: (f32, f32) = (0.01, 20.0);
+
+// Low-frequency oscillator waveforms
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LfoShape {
+    Sine,
+    Triangle,
+    Square,
+    // Ramps up from -1 to 1 each cycle
+    SawUp,
+    SawDown,
+}
+
+impl LfoShape {
+    pub fn from_index(index: usize) -> Option<LfoShape> {
+        [LfoShape::Sine, LfoShape::Triangle, LfoShape::Square, LfoShape::SawUp, LfoShape::SawDown]
+            .get(index)
+            .copied()
+    }
+}
+
+// Bipolar (-1..1) value of a waveform `phase` cycles in (wraps)
+// Every shape starts a cycle at its centre and rises, so phase offsets
+// line up across shapes
+#[inline]
+pub fn waveform(shape: LfoShape, phase: f32) -> f32 {
+    let p = phase.rem_euclid(1.0);
+    match shape {
+        LfoShape::Sine => (TAU * p).sin(),
+        LfoShape::Triangle => {
+            if p < 0.25 {
+                4.0 * p
+            } else if p < 0.75 {
+                2.0 - 4.0 * p
+            } else {
+                4.0 * p - 4.0
+            }
+        }
+        LfoShape::Square => {
+            if p < 0.5 {
+                1.0
+            } else {
+                -1.0
+            }
+        }
+        LfoShape::SawUp => {
+            if p < 0.5 {
+                2.0 * p
+            } else {
+                2.0 * p - 2.0
+            }
+        }
+        LfoShape::SawDown => -waveform(LfoShape::SawUp, p),
+    }
+}
+
+// Free-running oscillator for modulation effects
+// Modulated stages that need several related phases (chorus voices,
+// stereo channels) read one Lfo at offsets with value_at()
+#[derive(Clone, Debug)]
+pub struct Lfo {
+    sample_rate: f32,
+    rate: f32,
+    shape: LfoShape,
+    // Kept in f64 so very slow rates still advance accurately
+    phase: f64,
+}
+
+impl Lfo {
+    pub fn new(shape: LfoShape, rate: f32, sample_rate: f32) -> Lfo {
+        let mut lfo = Lfo { sample_rate, rate: 1.0, shape, phase: 0.0 };
+        lfo.set_rate(rate);
+        lfo
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    // Cycles per second
+    pub fn set_rate(&mut self, hz: f32) {
+        if hz.is_finite() {
+            self.rate = hz.clamp(LFO_RATE_RANGE.0, LFO_RATE_RANGE.1);
+        }
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.shape = shape;
+    }
+
+    pub fn shape(&self) -> LfoShape {
+        self.shape
+    }
+
+    // Position in the cycle, 0..1
+    pub fn phase(&self) -> f32 {
+        self.phase as f32
+    }
+
+    pub fn set_phase(&mut self, phase: f32) {
+        if phase.is_finite() {
+            self.phase = (phase as f64).rem_euclid(1.0);
+        }
+    }
+
+    // Value at the current phase plus `offset` cycles
+    #[inline]
+    pub fn value_at(&self, offset: f32) -> f32 {
+        waveform(self.shape, self.phase as f32 + offset)
+    }
+
+    // Move on by one sample
+    #[inline]
+    pub fn advance(&mut self) {
+        self.phase += self.rate as f64 / self.sample_rate as f64;
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+        }
+    }
+
+    // Current value, then advance
+    #[inline]
+    pub fn tick(&mut self) -> f32 {
+        let value = self.value_at(0.0);
+        self.advance();
+        value
+    }
+
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+}
@@ -1,178 +1,729 @@
 use wasm_bindgen::prelude::*;
 
+pub mod analyzer;
+pub mod biquad;
+pub mod cascade;
+pub mod chain;
+pub mod delay_line;
+pub mod effects;
+pub mod fft;
+pub mod lfo;
+pub mod oversampling;
+pub mod params;
+pub mod resample;
+pub mod smoothing;
+pub mod svf;
+pub mod vocoder;
+pub mod window;
+
+pub use analyzer::{SpectrumAnalyzer, SpectrumScale};
+pub use biquad::FilterType;
+pub use cascade::{FilterAlignment, FilterSlope};
+pub use delay_line::DelayInterpolation;
+pub use effects::{DetectorMode, EffectKind, ParametricEq, StereoDelayMode, WaveshaperCurve};
+pub use fft::RealFft;
+pub use lfo::LfoShape;
+pub use oversampling::OversamplingFactor;
+pub use params::ProcessorParams;
+pub use smoothing::SmoothingMode;
+pub use svf::FilterEngine;
+pub use window::WindowType;
+
+use chain::{EffectChain, EffectId, MAX_CHANNELS};
+use effects::{
+    create_effect, Compressor, Convolver, Distortion, Filter, Gain, Limiter, NoiseGate, Reverb, StereoDelay, Waveshaper,
+};
+use oversampling::{Oversampled, DEFAULT_OVERSAMPLING};
+use smoothing::DEFAULT_SMOOTHING_MS;
+
+// Largest block handed to an effect; longer buffers are split
+const MAX_BLOCK: usize = 4096;
+
 // Real-time Audio DSP Processor
 // Clean, simple, and works reliably
 #[wasm_bindgen]
 pub struct AudioProcessor {
     sample_rate: f32,
+    params: ProcessorParams,
+    smoothing_mode: SmoothingMode,
+    smoothing_ms: f32,
+    channels: usize,
+    // Planar copy of one block for interleaved and stereo input
+    planar: Vec<f32>,
     
-    // Filter state variables
-    lpf_x1: f32,
-    lpf_x2: f32,
-    lpf_y1: f32,
-    lpf_y2: f32,
+    // Effect chain: noise gate -> gain -> distortion -> LPF -> HPF -> EQ -> compressor -> stereo delay -> reverb -> limiter
+    chain: EffectChain,
     
-    hpf_x1: f32,
-    hpf_x2: f32,
-    hpf_y1: f32,
-    hpf_y2: f32,
+    // Handles of the built-in stages that ProcessorParams drive
+    gate_id: EffectId,
+    gain_id: EffectId,
+    distortion_id: EffectId,
+    lpf_id: EffectId,
+    hpf_id: EffectId,
+    eq_id: EffectId,
+    compressor_id: EffectId,
+    delay_id: EffectId,
+    reverb_id: EffectId,
+    limiter_id: EffectId,
     
-    // Delay buffer
-    delay_buffer: Vec<f32>,
-    delay_write_pos: usize,
+    // Taps the processed output for metering
+    analyzer: SpectrumAnalyzer,
 }
 
 #[wasm_bindgen]
 impl AudioProcessor {
     #[wasm_bindgen(constructor)]
     pub fn new(sample_rate: f32) -> AudioProcessor {
+        let mut chain = EffectChain::new(sample_rate, MAX_BLOCK);
+        let gate_id = chain.push(Box::new(NoiseGate::new(sample_rate)));
+        let gain_id = chain.push(Box::new(Gain::new()));
+        let distortion_id = chain.push(Box::new(Oversampled::new(Distortion::new(), DEFAULT_OVERSAMPLING)));
+        let lpf_id = chain.push(Box::new(Filter::low_pass(sample_rate)));
+        let hpf_id = chain.push(Box::new(Filter::high_pass(sample_rate)));
+        let eq_id = chain.push(Box::new(ParametricEq::new(sample_rate)));
+        let compressor_id = chain.push(Box::new(Compressor::new(sample_rate)));
+        let delay_id = chain.push(Box::new(StereoDelay::new(sample_rate)));
+        let reverb_id = chain.push(Box::new(Reverb::new(sample_rate)));
+        let limiter_id = chain.push(Box::new(Limiter::new(sample_rate)));
+        
         AudioProcessor {
             sample_rate,
-            lpf_x1: 0.0,
-            lpf_x2: 0.0,
-            lpf_y1: 0.0,
-            lpf_y2: 0.0,
-            hpf_x1: 0.0,
-            hpf_x2: 0.0,
-            hpf_y1: 0.0,
-            hpf_y2: 0.0,
-            delay_buffer: vec![0.0; sample_rate as usize],
-            delay_write_pos: 0,
+            params: ProcessorParams::default(),
+            smoothing_mode: SmoothingMode::Linear,
+            smoothing_ms: DEFAULT_SMOOTHING_MS,
+            channels: 1,
+            planar: vec![0.0; MAX_BLOCK],
+            chain,
+            gate_id,
+            gain_id,
+            distortion_id,
+            lpf_id,
+            hpf_id,
+            eq_id,
+            compressor_id,
+            delay_id,
+            reverb_id,
+            limiter_id,
+            analyzer: SpectrumAnalyzer::new(sample_rate),
         }
     }
     
-    pub fn process(
-        &mut self,
-        buffer: &mut [f32],
-        gain: f32,
-        lpf_cutoff: f32,
-        hpf_cutoff: f32,
-        delay_time: f32,
-        delay_feedback: f32,
-        delay_mix: f32,
-        distortion: f32,
-    ) {
-        // Calculate filter coefficients
-        let lpf_coeffs = self.calc_lpf(lpf_cutoff);
-        let hpf_coeffs = self.calc_hpf(hpf_cutoff);
-        
-        // Calculate delay samples
-        let delay_samples = ((delay_time * self.sample_rate) as usize)
-            .min(self.delay_buffer.len() - 1)
-            .max(1);
-        
-        // Process each sample
-        for sample in buffer.iter_mut() {
-            let mut x = *sample * gain;
-            
-            // Distortion
-            if distortion > 0.01 {
-                let drive = 1.0 + distortion * 8.0;
-                x = (x * drive).tanh() / drive.tanh();
-            }
-            
-            // Filters
-            x = Self::biquad(x, &lpf_coeffs, &mut self.lpf_x1, &mut self.lpf_x2, &mut self.lpf_y1, &mut self.lpf_y2);
-            x = Self::biquad(x, &hpf_coeffs, &mut self.hpf_x1, &mut self.hpf_x2, &mut self.hpf_y1, &mut self.hpf_y2);
-            
-            // Delay - SIMPLE AND CLEAN
-            if delay_time > 0.001 && delay_mix > 0.001 {
-                let read_pos = if self.delay_write_pos >= delay_samples {
-                    self.delay_write_pos - delay_samples
-                } else {
-                    self.delay_buffer.len() + self.delay_write_pos - delay_samples
-                };
-                
-                let delayed = self.delay_buffer[read_pos];
-                
-                // SIMPLE feedback - just reduce it A LOT
-                let fb = (delay_feedback * 0.25).min(0.6);
-                self.delay_buffer[self.delay_write_pos] = x + delayed * fb;
-                
-                // Mix
-                x = x * (1.0 - delay_mix * 0.5) + delayed * delay_mix * 0.5;
-                
-                self.delay_write_pos = (self.delay_write_pos + 1) % self.delay_buffer.len();
-            } else {
-                // No delay - write silence
-                self.delay_buffer[self.delay_write_pos] = 0.0;
-                self.delay_write_pos = (self.delay_write_pos + 1) % self.delay_buffer.len();
+    // Configure how parameter changes are smoothed (time in milliseconds)
+    pub fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.smoothing_mode = mode;
+        self.smoothing_ms = if time_ms.is_finite() { time_ms.max(0.0) } else { DEFAULT_SMOOTHING_MS };
+        self.chain.set_smoothing(mode, self.smoothing_ms);
+    }
+    
+    pub fn smoothing_time(&self) -> f32 {
+        self.smoothing_ms
+    }
+    
+    // Number of channels for process_interleaved/process_planar (1..=8)
+    // Each channel keeps its own filter and delay state
+    pub fn set_channels(&mut self, channels: usize) {
+        self.channels = channels.clamp(1, MAX_CHANNELS);
+        self.chain.set_channels(self.channels);
+        self.planar.resize(self.channels * MAX_BLOCK, 0.0);
+    }
+    
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+    
+    // How long the delay read head takes to reach a new delay time (milliseconds)
+    pub fn set_delay_glide(&mut self, time_ms: f32) {
+        self.with_stage(self.delay_id, |delay: &mut StereoDelay| delay.set_glide(time_ms));
+    }
+    
+    pub fn set_delay_interpolation(&mut self, interpolation: DelayInterpolation) {
+        self.with_stage(self.delay_id, |delay: &mut StereoDelay| delay.set_interpolation(interpolation));
+    }
+    
+    // Replace the whole parameter set (e.g. when switching presets)
+    pub fn set_params(&mut self, params: &ProcessorParams) {
+        self.params = *params;
+    }
+    
+    // Copy of the current parameters
+    pub fn params(&self) -> ProcessorParams {
+        self.params
+    }
+    
+    // Individual parameter setters - validated by ProcessorParams
+    pub fn set_gate_threshold(&mut self, value: f32) {
+        self.params.set_gate_threshold(value);
+    }
+    
+    pub fn set_gate_hysteresis(&mut self, value: f32) {
+        self.params.set_gate_hysteresis(value);
+    }
+    
+    pub fn set_gate_attack(&mut self, value: f32) {
+        self.params.set_gate_attack(value);
+    }
+    
+    pub fn set_gate_hold(&mut self, value: f32) {
+        self.params.set_gate_hold(value);
+    }
+    
+    pub fn set_gate_release(&mut self, value: f32) {
+        self.params.set_gate_release(value);
+    }
+    
+    pub fn set_gate_range(&mut self, value: f32) {
+        self.params.set_gate_range(value);
+    }
+    
+    pub fn set_gate_sidechain_hpf(&mut self, value: f32) {
+        self.params.set_gate_sidechain_hpf(value);
+    }
+    
+    pub fn set_gain(&mut self, value: f32) {
+        self.params.set_gain(value);
+    }
+    
+    pub fn set_lpf_cutoff(&mut self, value: f32) {
+        self.params.set_lpf_cutoff(value);
+    }
+    
+    pub fn set_hpf_cutoff(&mut self, value: f32) {
+        self.params.set_hpf_cutoff(value);
+    }
+    
+    pub fn set_lpf_q(&mut self, value: f32) {
+        self.params.set_lpf_q(value);
+    }
+    
+    pub fn set_hpf_q(&mut self, value: f32) {
+        self.params.set_hpf_q(value);
+    }
+    
+    pub fn set_lpf_resonance(&mut self, value: f32) {
+        self.params.set_lpf_resonance(value);
+    }
+    
+    pub fn set_hpf_resonance(&mut self, value: f32) {
+        self.params.set_hpf_resonance(value);
+    }
+    
+    pub fn set_lpf_slope(&mut self, value: FilterSlope) {
+        self.params.set_lpf_slope(value);
+    }
+    
+    pub fn set_hpf_slope(&mut self, value: FilterSlope) {
+        self.params.set_hpf_slope(value);
+    }
+    
+    pub fn set_filter_alignment(&mut self, value: FilterAlignment) {
+        self.params.set_filter_alignment(value);
+    }
+    
+    pub fn set_filter_engine(&mut self, value: FilterEngine) {
+        self.params.set_filter_engine(value);
+    }
+    
+    pub fn set_delay_time(&mut self, value: f32) {
+        self.params.set_delay_time(value);
+    }
+    
+    pub fn set_delay_feedback(&mut self, value: f32) {
+        self.params.set_delay_feedback(value);
+    }
+    
+    pub fn set_delay_mix(&mut self, value: f32) {
+        self.params.set_delay_mix(value);
+    }
+    
+    pub fn set_delay_safe_mode(&mut self, value: bool) {
+        self.params.set_delay_safe_mode(value);
+    }
+    
+    pub fn set_delay_mode(&mut self, value: StereoDelayMode) {
+        self.params.set_delay_mode(value);
+    }
+    
+    pub fn set_delay_offset(&mut self, value: f32) {
+        self.params.set_delay_offset(value);
+    }
+    
+    pub fn set_delay_cross_feedback(&mut self, value: f32) {
+        self.params.set_delay_cross_feedback(value);
+    }
+    
+    pub fn set_delay_width(&mut self, value: f32) {
+        self.params.set_delay_width(value);
+    }
+    
+    pub fn set_compressor_threshold(&mut self, value: f32) {
+        self.params.set_compressor_threshold(value);
+    }
+    
+    pub fn set_compressor_ratio(&mut self, value: f32) {
+        self.params.set_compressor_ratio(value);
+    }
+    
+    pub fn set_compressor_knee(&mut self, value: f32) {
+        self.params.set_compressor_knee(value);
+    }
+    
+    pub fn set_compressor_attack(&mut self, value: f32) {
+        self.params.set_compressor_attack(value);
+    }
+    
+    pub fn set_compressor_release(&mut self, value: f32) {
+        self.params.set_compressor_release(value);
+    }
+    
+    pub fn set_compressor_makeup(&mut self, value: f32) {
+        self.params.set_compressor_makeup(value);
+    }
+    
+    pub fn set_compressor_detector(&mut self, value: DetectorMode) {
+        self.params.set_compressor_detector(value);
+    }
+    
+    // Current compressor gain reduction in dB, for metering (0 if the stage was removed)
+    pub fn compressor_gain_reduction(&self) -> f32 {
+        self.chain.get_param(self.compressor_id, "gain_reduction").unwrap_or(0.0)
+    }
+    
+    pub fn set_reverb_mix(&mut self, value: f32) {
+        self.params.set_reverb_mix(value);
+    }
+    
+    pub fn set_reverb_room_size(&mut self, value: f32) {
+        self.params.set_reverb_room_size(value);
+    }
+    
+    pub fn set_reverb_decay(&mut self, value: f32) {
+        self.params.set_reverb_decay(value);
+    }
+    
+    pub fn set_reverb_damping(&mut self, value: f32) {
+        self.params.set_reverb_damping(value);
+    }
+    
+    pub fn set_reverb_pre_delay(&mut self, value: f32) {
+        self.params.set_reverb_pre_delay(value);
+    }
+    
+    pub fn set_reverb_diffusion(&mut self, value: f32) {
+        self.params.set_reverb_diffusion(value);
+    }
+    
+    pub fn set_distortion(&mut self, value: f32) {
+        self.params.set_distortion(value);
+    }
+    
+    pub fn set_distortion_curve(&mut self, value: WaveshaperCurve) {
+        self.params.set_distortion_curve(value);
+    }
+    
+    pub fn set_distortion_oversampling(&mut self, value: OversamplingFactor) {
+        self.params.set_distortion_oversampling(value);
+    }
+    
+    pub fn set_limiter_ceiling(&mut self, value: f32) {
+        self.params.set_limiter_ceiling(value);
+    }
+    
+    pub fn set_limiter_release(&mut self, value: f32) {
+        self.params.set_limiter_release(value);
+    }
+    
+    pub fn set_limiter_lookahead(&mut self, value: f32) {
+        self.params.set_limiter_lookahead(value);
+    }
+    
+    // Current limiter gain reduction in dB, for metering (0 if the stage was removed)
+    pub fn limiter_gain_reduction(&self) -> f32 {
+        self.chain.get_param(self.limiter_id, "gain_reduction").unwrap_or(0.0)
+    }
+    
+    // Parametric EQ bands of the built-in EQ stage (see ParametricEq for the band API)
+    pub fn eq_add_band(&mut self, filter_type: FilterType, frequency: f32, gain_db: f32, q: f32) -> Option<usize> {
+        let mut index = None;
+        self.with_stage(self.eq_id, |eq: &mut ParametricEq| {
+            index = eq.add_band(filter_type, frequency, gain_db, q);
+        });
+        index
+    }
+    
+    pub fn eq_remove_band(&mut self, index: usize) -> bool {
+        let mut removed = false;
+        self.with_stage(self.eq_id, |eq: &mut ParametricEq| removed = eq.remove_band(index));
+        removed
+    }
+    
+    pub fn eq_set_band(&mut self, index: usize, filter_type: FilterType, frequency: f32, gain_db: f32, q: f32) -> bool {
+        let mut set = false;
+        self.with_stage(self.eq_id, |eq: &mut ParametricEq| {
+            set = eq.set_band(index, filter_type, frequency, gain_db, q);
+        });
+        set
+    }
+    
+    pub fn eq_set_band_enabled(&mut self, index: usize, enabled: bool) -> bool {
+        let mut set = false;
+        self.with_stage(self.eq_id, |eq: &mut ParametricEq| set = eq.set_band_enabled(index, enabled));
+        set
+    }
+    
+    pub fn eq_clear(&mut self) {
+        self.with_stage(self.eq_id, |eq: &mut ParametricEq| eq.clear());
+    }
+    
+    pub fn eq_band_count(&mut self) -> usize {
+        let mut count = 0;
+        self.with_stage(self.eq_id, |eq: &mut ParametricEq| count = eq.band_count());
+        count
+    }
+    
+    // Replace the whole EQ, e.g. one built on the JS side for a preset
+    pub fn set_eq(&mut self, eq: &ParametricEq) {
+        let (mode, ms) = (self.smoothing_mode, self.smoothing_ms);
+        self.with_stage(self.eq_id, |current: &mut ParametricEq| {
+            *current = eq.clone();
+            current.set_smoothing(mode, ms);
+        });
+    }
+    
+    // Copy of the current EQ (e.g. for drawing its response curve)
+    pub fn eq(&mut self) -> Option<ParametricEq> {
+        self.chain.get_mut_as::<ParametricEq>(self.eq_id).map(|eq| eq.clone())
+    }
+    
+    // Effect chain - effects are addressed by the handle returned when added
+    pub fn add_effect(&mut self, kind: EffectKind) -> EffectId {
+        self.chain.push(create_effect(kind, self.sample_rate))
+    }
+    
+    pub fn insert_effect(&mut self, index: usize, kind: EffectKind) -> EffectId {
+        self.chain.insert(index, create_effect(kind, self.sample_rate))
+    }
+    
+    pub fn remove_effect(&mut self, id: EffectId) -> bool {
+        self.chain.remove(id).is_some()
+    }
+    
+    pub fn move_effect(&mut self, id: EffectId, index: usize) -> bool {
+        self.chain.move_to(id, index)
+    }
+    
+    pub fn set_effect_bypass(&mut self, id: EffectId, bypassed: bool) -> bool {
+        self.chain.set_bypass(id, bypassed)
+    }
+    
+    pub fn is_effect_bypassed(&self, id: EffectId) -> Option<bool> {
+        self.chain.is_bypassed(id)
+    }
+    
+    pub fn set_effect_param(&mut self, id: EffectId, name: &str, value: f32) -> bool {
+        self.chain.set_param(id, name, value)
+    }
+    
+    pub fn get_effect_param(&self, id: EffectId, name: &str) -> Option<f32> {
+        self.chain.get_param(id, name)
+    }
+    
+    // Linked effects (the default) apply every parameter change to all channels
+    // Unlinked ones are set per channel and no longer follow ProcessorParams
+    pub fn set_effect_linked(&mut self, id: EffectId, linked: bool) -> bool {
+        self.chain.set_linked(id, linked)
+    }
+    
+    pub fn is_effect_linked(&self, id: EffectId) -> Option<bool> {
+        self.chain.is_linked(id)
+    }
+    
+    pub fn set_effect_channel_param(&mut self, id: EffectId, channel: usize, name: &str, value: f32) -> bool {
+        self.chain.set_channel_param(id, channel, name, value)
+    }
+    
+    pub fn get_effect_channel_param(&self, id: EffectId, channel: usize, name: &str) -> Option<f32> {
+        self.chain.get_channel_param(id, channel, name)
+    }
+    
+    pub fn effect_name(&self, id: EffectId) -> Option<String> {
+        self.chain.get(id).map(|e| e.name().to_string())
+    }
+    
+    // Load an impulse response into a Convolver effect on every channel
+    // `sample_rate` is the IR's own rate; it is resampled to the processor's
+    pub fn load_impulse_response(&mut self, id: EffectId, ir: Vec<f32>, sample_rate: f32) -> bool {
+        self.chain.for_each_as(id, |c: &mut Convolver| c.load_impulse_response(&ir, sample_rate))
+    }
+    
+    // Load one channel's impulse response (true-stereo IRs, speaker pairs)
+    pub fn load_channel_impulse_response(&mut self, id: EffectId, channel: usize, ir: Vec<f32>, sample_rate: f32) -> bool {
+        match self.chain.get_channel_mut_as::<Convolver>(id, channel) {
+            Some(convolver) => {
+                convolver.load_impulse_response(&ir, sample_rate);
+                true
             }
-            
-            // Soft limit
-            x = x.clamp(-0.95, 0.95);
-            
-            *sample = x;
+            None => false,
         }
     }
     
-    fn calc_lpf(&self, cutoff: f32) -> [f32; 5] {
-        let cutoff = cutoff.max(100.0).min(self.sample_rate * 0.45);
-        let q = 0.707;
-        let w0 = 2.0 * std::f32::consts::PI * cutoff / self.sample_rate;
-        let cos_w0 = w0.cos();
-        let sin_w0 = w0.sin();
-        let alpha = sin_w0 / (2.0 * q);
-        
-        let b0 = (1.0 - cos_w0) / 2.0;
-        let b1 = 1.0 - cos_w0;
-        let b2 = (1.0 - cos_w0) / 2.0;
-        let a0 = 1.0 + alpha;
-        let a1 = -2.0 * cos_w0;
-        let a2 = 1.0 - alpha;
-        
-        [b0/a0, b1/a0, b2/a0, a1/a0, a2/a0]
+    pub fn clear_impulse_response(&mut self, id: EffectId) -> bool {
+        self.chain.for_each_as(id, |c: &mut Convolver| c.clear_impulse_response())
     }
     
-    fn calc_hpf(&self, cutoff: f32) -> [f32; 5] {
-        let cutoff = cutoff.max(20.0).min(self.sample_rate * 0.45);
-        let q = 0.707;
-        let w0 = 2.0 * std::f32::consts::PI * cutoff / self.sample_rate;
-        let cos_w0 = w0.cos();
-        let sin_w0 = w0.sin();
-        let alpha = sin_w0 / (2.0 * q);
-        
-        let b0 = (1.0 + cos_w0) / 2.0;
-        let b1 = -(1.0 + cos_w0);
-        let b2 = (1.0 + cos_w0) / 2.0;
-        let a0 = 1.0 + alpha;
-        let a1 = -2.0 * cos_w0;
-        let a2 = 1.0 - alpha;
-        
-        [b0/a0, b1/a0, b2/a0, a1/a0, a2/a0]
+    // Transfer table for a Waveshaper effect using WaveshaperCurve::Table
+    // Entries evenly cover the driven input from -1 to 1
+    pub fn set_waveshaper_table(&mut self, id: EffectId, table: Vec<f32>) -> bool {
+        let mut accepted = false;
+        self.chain.for_each_as(id, |w: &mut Waveshaper| accepted = w.set_table(&table));
+        accepted
     }
     
-    fn biquad(input: f32, c: &[f32; 5], x1: &mut f32, x2: &mut f32, y1: &mut f32, y2: &mut f32) -> f32 {
-        let out = c[0] * input + c[1] * *x1 + c[2] * *x2 - c[3] * *y1 - c[4] * *y2;
-        *x2 = *x1;
-        *x1 = input;
-        *y2 = *y1;
-        *y1 = out;
-        out
+    // Handles in processing order
+    pub fn effect_ids(&self) -> Vec<EffectId> {
+        self.chain.ids()
     }
     
-    pub fn reset(&mut self) {
-        self.lpf_x1 = 0.0;
-        self.lpf_x2 = 0.0;
-        self.lpf_y1 = 0.0;
-        self.lpf_y2 = 0.0;
-        self.hpf_x1 = 0.0;
-        self.hpf_x2 = 0.0;
-        self.hpf_y1 = 0.0;
-        self.hpf_y2 = 0.0;
-        for s in self.delay_buffer.iter_mut() {
-            *s = 0.0;
+    // Handle of a built-in stage (None for kinds without one)
+    pub fn builtin_effect_id(&self, kind: EffectKind) -> Option<EffectId> {
+        match kind {
+            EffectKind::NoiseGate => Some(self.gate_id),
+            EffectKind::Gain => Some(self.gain_id),
+            EffectKind::Distortion => Some(self.distortion_id),
+            EffectKind::LowPass => Some(self.lpf_id),
+            EffectKind::HighPass => Some(self.hpf_id),
+            EffectKind::Equalizer => Some(self.eq_id),
+            EffectKind::Compressor => Some(self.compressor_id),
+            EffectKind::StereoDelay => Some(self.delay_id),
+            EffectKind::Reverb => Some(self.reverb_id),
+            EffectKind::Limiter => Some(self.limiter_id),
+            EffectKind::Delay
+            | EffectKind::Clipper
+            | EffectKind::Convolver
+            | EffectKind::Waveshaper
+            | EffectKind::Chorus
+            | EffectKind::Flanger
+            | EffectKind::Phaser
+            | EffectKind::Tremolo
+            | EffectKind::AutoPan
+            | EffectKind::RingModulator
+            | EffectKind::PitchShifter => None,
+        }
+    }
+    
+    // Total latency of the effects, in samples (bypassed ones included)
+    pub fn latency(&self) -> usize {
+        self.chain.latency()
+    }
+    
+    // Mono buffer - always uses channel 0's state
+    pub fn process(&mut self, buffer: &mut [f32]) {
+        self.apply_params();
+        self.chain.process(buffer);
+        self.analyzer.push(buffer);
+    }
+    
+    // Interleaved frames (L R L R ...) with `channels()` samples per frame
+    pub fn process_interleaved(&mut self, buffer: &mut [f32]) {
+        self.apply_params();
+        let channels = self.channels;
+        for frames in buffer.chunks_mut(channels * MAX_BLOCK) {
+            let len = frames.len() / channels;
+            let planar = &mut self.planar[..channels * len];
+            for (i, frame) in frames.chunks_exact(channels).enumerate() {
+                for (c, &x) in frame.iter().enumerate() {
+                    planar[c * len + i] = x;
+                }
+            }
+            self.chain.process_planar(planar, channels);
+            self.analyzer.push_planar(planar, channels);
+            for (i, frame) in frames.chunks_exact_mut(channels).enumerate() {
+                for (c, x) in frame.iter_mut().enumerate() {
+                    *x = planar[c * len + i];
+                }
+            }
         }
-        self.delay_write_pos = 0;
     }
     
-    // Get delay buffer size in bytes (for memory monitoring)
-    pub fn get_buffer_size(&self) -> usize {
-        self.delay_buffer.len() * std::mem::size_of::<f32>()
+    // Planar buffer: `channels()` equal-length runs, one after another
+    pub fn process_planar(&mut self, buffer: &mut [f32]) {
+        self.apply_params();
+        self.chain.process_planar(buffer, self.channels);
+        self.analyzer.push_planar(buffer, self.channels);
+    }
+    
+    // Separate left/right buffers, e.g. two getChannelData() arrays
+    // Needs set_channels(2) first: a mono processor only processes `left`
+    // and leaves `right` untouched, as switching here would allocate
+    pub fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        if self.channels < 2 {
+            self.process(left);
+            return;
+        }
+        self.apply_params();
+        let frames = left.len().min(right.len());
+        let mut start = 0;
+        while start < frames {
+            let len = (frames - start).min(MAX_BLOCK);
+            let planar = &mut self.planar[..2 * len];
+            planar[..len].copy_from_slice(&left[start..start + len]);
+            planar[len..].copy_from_slice(&right[start..start + len]);
+            self.chain.process_planar(planar, 2);
+            self.analyzer.push_planar(planar, 2);
+            left[start..start + len].copy_from_slice(&planar[..len]);
+            right[start..start + len].copy_from_slice(&planar[len..]);
+            start += len;
+        }
+    }
+    
+    pub fn reset(&mut self) {
+        self.chain.reset();
+        self.analyzer.reset();
+    }
+    
+    // Spectrum of the processed output (all channels averaged)
+    // Call once per animation frame: returns smoothed band levels in dBFS
+    pub fn analyze_spectrum(&mut self) -> Vec<f32> {
+        self.analyzer.analyze();
+        self.analyzer.magnitudes_db()
+    }
+    
+    // Held peaks from the last analyze_spectrum(), in dBFS
+    pub fn spectrum_peaks(&self) -> Vec<f32> {
+        self.analyzer.peaks_db()
+    }
+    
+    // Centre frequency of each band in Hz
+    pub fn spectrum_frequencies(&self) -> Vec<f32> {
+        self.analyzer.frequencies()
+    }
+    
+    // Loudest frequency in the output at the last analysis (0 when silent)
+    pub fn dominant_frequency(&self) -> f32 {
+        self.analyzer.dominant_frequency()
+    }
+    
+    pub fn set_spectrum_scale(&mut self, scale: SpectrumScale) {
+        self.analyzer.set_scale(scale);
+    }
+    
+    pub fn set_spectrum_bands(&mut self, count: usize) {
+        self.analyzer.set_band_count(count);
+    }
+    
+    pub fn set_spectrum_range(&mut self, min_hz: f32, max_hz: f32) {
+        self.analyzer.set_frequency_range(min_hz, max_hz);
+    }
+    
+    pub fn set_spectrum_fft_size(&mut self, size: usize) {
+        self.analyzer.set_fft_size(size);
+    }
+    
+    pub fn set_spectrum_window(&mut self, window: WindowType) {
+        self.analyzer.set_window(window);
+    }
+    
+    // Level rise and fall times in milliseconds
+    pub fn set_spectrum_ballistics(&mut self, attack_ms: f32, release_ms: f32) {
+        self.analyzer.set_attack(attack_ms);
+        self.analyzer.set_release(release_ms);
+    }
+    
+    // Peak hold time in milliseconds, then fall rate in dB per second
+    pub fn set_spectrum_peak_hold(&mut self, hold_ms: f32, decay_db_per_second: f32) {
+        self.analyzer.set_peak_hold(hold_ms);
+        self.analyzer.set_peak_decay(decay_db_per_second);
+    }
+    
+    // Get delay buffer size in bytes, all channels (for memory monitoring)
+    pub fn get_buffer_size(&mut self) -> usize {
+        let mut len = 0;
+        self.chain.for_each_as(self.delay_id, |d: &mut StereoDelay| len += d.buffer_len());
+        len * std::mem::size_of::<f32>()
     }
     
     // Get total memory used by this struct
-    pub fn get_memory_usage(&self) -> usize {
-        std::mem::size_of::<Self>() + self.delay_buffer.capacity() * std::mem::size_of::<f32>()
+    pub fn get_memory_usage(&mut self) -> usize {
+        let mut capacity = self.planar.capacity();
+        self.chain.for_each_as(self.delay_id, |d: &mut StereoDelay| capacity += d.buffer_capacity());
+        std::mem::size_of::<Self>() + capacity * std::mem::size_of::<f32>()
+    }
+}
+
+impl AudioProcessor {
+    // Hand ProcessorParams to the built-in stages
+    // Stages removed from the chain are simply skipped
+    fn apply_params(&mut self) {
+        let p = self.params;
+        self.with_stage(self.gate_id, |gate: &mut NoiseGate| {
+            gate.set_threshold(p.gate_threshold());
+            gate.set_hysteresis(p.gate_hysteresis());
+            gate.set_attack(p.gate_attack());
+            gate.set_hold(p.gate_hold());
+            gate.set_release(p.gate_release());
+            gate.set_range(p.gate_range());
+            gate.set_sidechain_hpf(p.gate_sidechain_hpf());
+        });
+        self.with_stage(self.gain_id, |gain: &mut Gain| gain.set_gain(p.gain()));
+        self.with_stage(self.distortion_id, |distortion: &mut Distortion| {
+            distortion.set_amount(p.distortion());
+            distortion.set_curve(p.distortion_curve());
+        });
+        // The wrapper hands out the inner Distortion, so the ratio goes by name
+        if self.chain.is_linked(self.distortion_id) == Some(true) {
+            self.chain.set_param(self.distortion_id, "oversampling", p.distortion_oversampling() as u32 as f32);
+        }
+        for (id, cutoff, q, resonance, slope) in [
+            (self.lpf_id, p.lpf_cutoff(), p.lpf_q(), p.lpf_resonance(), p.lpf_slope()),
+            (self.hpf_id, p.hpf_cutoff(), p.hpf_q(), p.hpf_resonance(), p.hpf_slope()),
+        ] {
+            self.with_stage(id, |filter: &mut Filter| {
+                filter.set_cutoff(cutoff);
+                filter.set_q(q);
+                filter.set_resonance(resonance);
+                filter.set_slope(slope);
+                filter.set_alignment(p.filter_alignment());
+                filter.set_engine(p.filter_engine());
+            });
+        }
+        self.with_stage(self.compressor_id, |compressor: &mut Compressor| {
+            compressor.set_threshold(p.compressor_threshold());
+            compressor.set_ratio(p.compressor_ratio());
+            compressor.set_knee(p.compressor_knee());
+            compressor.set_attack(p.compressor_attack());
+            compressor.set_release(p.compressor_release());
+            compressor.set_makeup(p.compressor_makeup());
+            compressor.set_detector(p.compressor_detector());
+        });
+        self.with_stage(self.delay_id, |delay: &mut StereoDelay| {
+            delay.set_mode(p.delay_mode());
+            delay.set_time_left(p.delay_time());
+            delay.set_time_right(p.delay_time() + p.delay_offset());
+            delay.set_cross_feedback(p.delay_cross_feedback());
+            delay.set_width(p.delay_width());
+            delay.set_feedback(p.delay_feedback());
+            delay.set_mix(p.delay_mix());
+            delay.set_safe_mode(p.delay_safe_mode());
+        });
+        self.with_stage(self.reverb_id, |reverb: &mut Reverb| {
+            reverb.set_mix(p.reverb_mix());
+            reverb.set_room_size(p.reverb_room_size());
+            reverb.set_decay(p.reverb_decay());
+            reverb.set_damping(p.reverb_damping());
+            reverb.set_pre_delay(p.reverb_pre_delay());
+            reverb.set_diffusion(p.reverb_diffusion());
+        });
+        self.with_stage(self.limiter_id, |limiter: &mut Limiter| {
+            limiter.set_ceiling(p.limiter_ceiling());
+            limiter.set_release(p.limiter_release());
+            limiter.set_lookahead(p.limiter_lookahead());
+        });
+    }
+    
+    // Run `f` on every channel of a linked built-in stage
+    // Stages that were removed or unlinked are left alone
+    fn with_stage<T: 'static>(&mut self, id: EffectId, f: impl FnMut(&mut T)) {
+        if self.chain.is_linked(id) == Some(true) {
+            self.chain.for_each_as(id, f);
+        }
     }
 }
@@ -0,0 +1,374 @@
+use std::any::Any;
+use std::f32::consts::PI;
+
+use wasm_bindgen::prelude::*;
+
+use crate::effects::{param_index, Effect};
+use crate::smoothing::{SmoothingMode, DEFAULT_SMOOTHING_MS};
+
+// Kaiser window shape of the halfband filters (~80 dB stopband)
+const KAISER_BETA: f32 = 8.0;
+// Non-zero taps per side for each 2x stage, steepest first: the first
+// stage has the narrowest transition band, later ones only have to reject
+// images far above the audio band
+const STAGE_TAPS: [usize; 3] = [16, 6, 4];
+
+// Ratio the distortion stages run at unless told otherwise
+pub const DEFAULT_OVERSAMPLING: OversamplingFactor = OversamplingFactor::X4;
+
+// Oversampling ratio for nonlinear stages
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OversamplingFactor {
+    // Off - runs at the base rate with no latency
+    X1,
+    X2,
+    X4,
+    X8,
+}
+
+impl OversamplingFactor {
+    pub fn from_index(index: usize) -> Option<OversamplingFactor> {
+        [OversamplingFactor::X1, OversamplingFactor::X2, OversamplingFactor::X4, OversamplingFactor::X8]
+            .get(index)
+            .copied()
+    }
+
+    // Number of 2x stages
+    pub fn stages(self) -> usize {
+        self as usize
+    }
+
+    pub fn ratio(self) -> usize {
+        1 << self.stages()
+    }
+}
+
+// One 2x up/down stage built on a linear-phase halfband FIR
+//
+// Every other tap of a halfband filter is zero and the centre tap is 0.5,
+// so each polyphase branch is either a short FIR or a plain delay.
+#[derive(Clone, Debug)]
+struct Halfband {
+    // Even-indexed taps (the non-zero sinc taps), reversed to line up with
+    // the histories below (newest sample last)
+    taps: Vec<f32>,
+    half: usize,
+    up: Vec<f32>,
+    down_even: Vec<f32>,
+    down_odd: Vec<f32>,
+    // Extra output delay that keeps the stage latency a whole number of
+    // base-rate samples
+    pad: Vec<f32>,
+    pad_pos: usize,
+}
+
+impl Halfband {
+    // `half` non-zero taps per side; `base_step` is this stage's low-rate
+    // sample length in base-rate samples
+    fn new(half: usize, base_step: usize) -> Halfband {
+        let centre = 2 * half - 1;
+        let taps: Vec<f32> = (0..2 * half)
+            .map(|j| {
+                let t = (2 * j) as f32 - centre as f32;
+                let sinc = (PI * t * 0.5).sin() / (PI * t * 0.5);
+                let r = t / (centre + 1) as f32;
+                0.5 * sinc * bessel_i0(KAISER_BETA * (1.0 - r * r).sqrt()) / bessel_i0(KAISER_BETA)
+            })
+            .collect();
+        // Unity gain at DC: the sinc taps carry half, the centre tap the other half
+        let sum: f32 = taps.iter().sum();
+        let taps = taps.iter().rev().map(|h| h * 0.5 / sum).collect();
+        let pad = (base_step - centre % base_step) % base_step;
+        Halfband {
+            taps,
+            half,
+            up: vec![0.0; 2 * half],
+            down_even: vec![0.0; 2 * half],
+            down_odd: vec![0.0; half + 1],
+            pad: vec![0.0; pad],
+            pad_pos: 0,
+        }
+    }
+
+    // Latency of up + down in this stage's low-rate samples
+    fn latency(&self) -> usize {
+        2 * self.half - 1 + self.pad.len()
+    }
+
+    #[inline]
+    fn upsample(&mut self, x: f32) -> (f32, f32) {
+        push(&mut self.up, x);
+        let even = 2.0 * dot(&self.taps, &self.up);
+        let odd = self.up[self.up.len() - self.half];
+        (even, odd)
+    }
+
+    #[inline]
+    fn downsample(&mut self, even: f32, odd: f32) -> f32 {
+        push(&mut self.down_even, even);
+        push(&mut self.down_odd, odd);
+        let y = dot(&self.taps, &self.down_even) + 0.5 * self.down_odd[0];
+        if self.pad.is_empty() {
+            return y;
+        }
+        let out = std::mem::replace(&mut self.pad[self.pad_pos], y);
+        self.pad_pos = (self.pad_pos + 1) % self.pad.len();
+        out
+    }
+
+    fn reset(&mut self) {
+        self.up.fill(0.0);
+        self.down_even.fill(0.0);
+        self.down_odd.fill(0.0);
+        self.pad.fill(0.0);
+        self.pad_pos = 0;
+    }
+}
+
+#[inline]
+fn push(history: &mut [f32], x: f32) {
+    history.copy_within(1.., 0);
+    history[history.len() - 1] = x;
+}
+
+#[inline]
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b.iter()).map(|(a, b)| a * b).sum()
+}
+
+// Zeroth-order modified Bessel function (series), for the Kaiser window
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x * 0.5;
+    for k in 1..32 {
+        term *= (half / k as f32) * (half / k as f32);
+        sum += term;
+        if term < sum * 1e-9 {
+            break;
+        }
+    }
+    sum
+}
+
+// Up- and downsampler for one channel: a cascade of 2x halfband stages
+// Built for a maximum ratio; lower ones run on the first stages only
+#[derive(Clone, Debug)]
+pub struct Oversampler {
+    factor: OversamplingFactor,
+    stages: Vec<Halfband>,
+    // Ping-pong buffers for the intermediate rates
+    work: [Vec<f32>; 2],
+}
+
+impl Oversampler {
+    pub fn new(factor: OversamplingFactor, max_block: usize) -> Oversampler {
+        let stages = (0..factor.stages()).map(|s| Halfband::new(STAGE_TAPS[s], 1 << s)).collect();
+        let len = max_block * factor.ratio();
+        Oversampler { factor, stages, work: [vec![0.0; len], vec![0.0; len]] }
+    }
+
+    pub fn factor(&self) -> OversamplingFactor {
+        self.factor
+    }
+
+    // Switch the active ratio without allocating and clear the filters
+    // Capped at the ratio this was created for
+    pub fn set_factor(&mut self, factor: OversamplingFactor) {
+        let stages = factor.stages().min(self.stages.len());
+        self.factor = OversamplingFactor::from_index(stages).unwrap_or(factor);
+        self.reset();
+    }
+
+    // Round-trip latency in base-rate samples
+    pub fn latency(&self) -> usize {
+        self.stages[..self.factor.stages()].iter().enumerate().map(|(s, stage)| stage.latency() >> s).sum()
+    }
+
+    // Upsample `input` and return the oversampled block (input.len() * ratio)
+    pub fn upsample(&mut self, input: &[f32]) -> &mut [f32] {
+        let mut len = input.len();
+        let [a, b] = &mut self.work;
+        a[..len].copy_from_slice(input);
+        let (mut src, mut dst) = (a, b);
+        for stage in self.stages[..self.factor.stages()].iter_mut() {
+            for (i, &x) in src[..len].iter().enumerate() {
+                let (even, odd) = stage.upsample(x);
+                dst[2 * i] = even;
+                dst[2 * i + 1] = odd;
+            }
+            len *= 2;
+            std::mem::swap(&mut src, &mut dst);
+        }
+        &mut src[..len]
+    }
+
+    // Downsample the block returned by the last upsample() into `output`
+    pub fn downsample(&mut self, output: &mut [f32]) {
+        let stages = self.factor.stages();
+        let [a, b] = &mut self.work;
+        // The oversampled block sits in whichever buffer upsample() ended on
+        let (mut src, mut dst) = if stages.is_multiple_of(2) { (a, b) } else { (b, a) };
+        let mut len = output.len() << stages;
+        for stage in self.stages[..stages].iter_mut().rev() {
+            len /= 2;
+            for i in 0..len {
+                dst[i] = stage.downsample(src[2 * i], src[2 * i + 1]);
+            }
+            std::mem::swap(&mut src, &mut dst);
+        }
+        output.copy_from_slice(&src[..output.len()]);
+    }
+
+    pub fn reset(&mut self) {
+        for stage in self.stages.iter_mut() {
+            stage.reset();
+        }
+    }
+}
+
+// Runs an effect at 2x/4x/8x the processor rate so the harmonics a
+// nonlinear stage creates above Nyquist are filtered out instead of
+// folding back as aliasing.
+// The wrapped effect keeps its own name and params (plus "oversampling",
+// an OversamplingFactor index) and as_any_mut() reaches it directly, so
+// typed access works the same whether or not it is wrapped.
+#[derive(Clone, Debug)]
+pub struct Oversampled<E> {
+    inner: E,
+    factor: OversamplingFactor,
+    sample_rate: f32,
+    max_block: usize,
+    // Allocated for X8 so set_factor never allocates
+    left: Oversampler,
+    right: Oversampler,
+    // Last set_smoothing, re-applied when the inner effect is re-prepared
+    smoothing: (SmoothingMode, f32),
+}
+
+impl<E: Effect + Clone + 'static> Oversampled<E> {
+    pub fn new(inner: E, factor: OversamplingFactor) -> Oversampled<E> {
+        Oversampled {
+            inner,
+            factor,
+            sample_rate: 0.0,
+            max_block: 0,
+            left: Oversampler::new(OversamplingFactor::X1, 0),
+            right: Oversampler::new(OversamplingFactor::X1, 0),
+            smoothing: (SmoothingMode::Linear, DEFAULT_SMOOTHING_MS),
+        }
+    }
+
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut E {
+        &mut self.inner
+    }
+
+    pub fn factor(&self) -> OversamplingFactor {
+        self.factor
+    }
+
+    // Switches the oversamplers and re-prepares the wrapped effect at the
+    // new rate; safe on the audio thread once prepared
+    pub fn set_factor(&mut self, factor: OversamplingFactor) {
+        if factor != self.factor {
+            self.factor = factor;
+            self.left.set_factor(factor);
+            self.right.set_factor(factor);
+            if self.max_block > 0 {
+                self.prepare_inner();
+            }
+        }
+    }
+
+    fn prepare_inner(&mut self) {
+        let ratio = self.factor.ratio();
+        self.inner.prepare(self.sample_rate * ratio as f32, self.max_block * ratio);
+        let (mode, time_ms) = self.smoothing;
+        self.inner.set_smoothing(mode, time_ms);
+    }
+}
+
+impl<E: Effect + Clone + 'static> Effect for Oversampled<E> {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn prepare(&mut self, sample_rate: f32, max_block: usize) {
+        self.sample_rate = sample_rate;
+        self.max_block = max_block;
+        self.left = Oversampler::new(OversamplingFactor::X8, max_block);
+        self.right = Oversampler::new(OversamplingFactor::X8, max_block);
+        self.left.set_factor(self.factor);
+        self.right.set_factor(self.factor);
+        self.prepare_inner();
+    }
+
+    fn process(&mut self, buffer: &mut [f32]) {
+        if self.factor == OversamplingFactor::X1 {
+            return self.inner.process(buffer);
+        }
+        let high = self.left.upsample(buffer);
+        self.inner.process(high);
+        self.left.downsample(buffer);
+    }
+
+    fn is_stereo(&self) -> bool {
+        self.inner.is_stereo()
+    }
+
+    fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        if self.factor == OversamplingFactor::X1 {
+            return self.inner.process_stereo(left, right);
+        }
+        let high_left = self.left.upsample(left);
+        let high_right = self.right.upsample(right);
+        self.inner.process_stereo(high_left, high_right);
+        self.left.downsample(left);
+        self.right.downsample(right);
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.left.reset();
+        self.right.reset();
+    }
+
+    fn latency(&self) -> usize {
+        let ratio = self.factor.ratio();
+        self.left.latency() + self.inner.latency().div_ceil(ratio)
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "oversampling" => match param_index(value).and_then(OversamplingFactor::from_index) {
+                Some(factor) => {
+                    self.set_factor(factor);
+                    true
+                }
+                None => false,
+            },
+            _ => self.inner.set_param(name, value),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "oversampling" => Some(self.factor as u32 as f32),
+            _ => self.inner.get_param(name),
+        }
+    }
+
+    fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.smoothing = (mode, time_ms);
+        self.inner.set_smoothing(mode, time_ms);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self.inner.as_any_mut()
+    }
+}
@@ -0,0 +1,637 @@
+use wasm_bindgen::prelude::*;
+
+use crate::biquad::{BUTTERWORTH_Q, Q_RANGE};
+use crate::cascade::{FilterAlignment, FilterSlope};
+use crate::effects::compressor::{
+    COMPRESSOR_ATTACK_RANGE, COMPRESSOR_KNEE_RANGE, COMPRESSOR_MAKEUP_RANGE, COMPRESSOR_RATIO_RANGE,
+    COMPRESSOR_RELEASE_RANGE, COMPRESSOR_THRESHOLD_RANGE,
+};
+use crate::effects::limiter::{LIMITER_CEILING_RANGE, LIMITER_LOOKAHEAD_RANGE, LIMITER_RELEASE_RANGE};
+use crate::effects::noise_gate::{
+    GATE_ATTACK_RANGE, GATE_HOLD_RANGE, GATE_HYSTERESIS_RANGE, GATE_RANGE_RANGE, GATE_RELEASE_RANGE,
+    GATE_SIDECHAIN_RANGE, GATE_THRESHOLD_RANGE,
+};
+use crate::effects::reverb::{REVERB_DECAY_RANGE, REVERB_PRE_DELAY_RANGE};
+use crate::effects::{DetectorMode, StereoDelayMode, WaveshaperCurve};
+use crate::oversampling::{OversamplingFactor, DEFAULT_OVERSAMPLING};
+use crate::svf::FilterEngine;
+
+// Parameter set for AudioProcessor
+// Lives on the processor so the UI can change one value at a time
+// instead of resending everything from the audio callback
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProcessorParams {
+    gate_threshold: f32,
+    gate_hysteresis: f32,
+    gate_attack: f32,
+    gate_hold: f32,
+    gate_release: f32,
+    gate_range: f32,
+    gate_sidechain_hpf: f32,
+    gain: f32,
+    lpf_cutoff: f32,
+    hpf_cutoff: f32,
+    lpf_q: f32,
+    hpf_q: f32,
+    lpf_resonance: f32,
+    hpf_resonance: f32,
+    lpf_slope: FilterSlope,
+    hpf_slope: FilterSlope,
+    filter_alignment: FilterAlignment,
+    filter_engine: FilterEngine,
+    delay_time: f32,
+    delay_feedback: f32,
+    delay_mix: f32,
+    delay_safe_mode: bool,
+    delay_mode: StereoDelayMode,
+    delay_offset: f32,
+    delay_cross_feedback: f32,
+    delay_width: f32,
+    compressor_threshold: f32,
+    compressor_ratio: f32,
+    compressor_knee: f32,
+    compressor_attack: f32,
+    compressor_release: f32,
+    compressor_makeup: f32,
+    compressor_detector: DetectorMode,
+    reverb_mix: f32,
+    reverb_room_size: f32,
+    reverb_decay: f32,
+    reverb_damping: f32,
+    reverb_pre_delay: f32,
+    reverb_diffusion: f32,
+    distortion: f32,
+    distortion_curve: WaveshaperCurve,
+    distortion_oversampling: OversamplingFactor,
+    limiter_ceiling: f32,
+    limiter_release: f32,
+    limiter_lookahead: f32,
+}
+
+// Valid ranges - setters clamp into these and ignore NaN/inf
+pub const GAIN_RANGE: (f32, f32) = (0.0, 4.0);
+pub const CUTOFF_RANGE: (f32, f32) = (20.0, 24000.0);
+pub const DELAY_TIME_RANGE: (f32, f32) = (0.0, 1.0);
+pub const DELAY_OFFSET_RANGE: (f32, f32) = (-0.5, 0.5);
+pub const UNIT_RANGE: (f32, f32) = (0.0, 1.0);
+
+fn validate(value: f32, current: f32, range: (f32, f32)) -> f32 {
+    if value.is_finite() {
+        value.clamp(range.0, range.1)
+    } else {
+        current
+    }
+}
+
+impl Default for ProcessorParams {
+    fn default() -> Self {
+        ProcessorParams {
+            gate_threshold: -100.0,
+            gate_hysteresis: 6.0,
+            gate_attack: 1.0,
+            gate_hold: 50.0,
+            gate_release: 150.0,
+            gate_range: -80.0,
+            gate_sidechain_hpf: 0.0,
+            gain: 1.0,
+            lpf_cutoff: 20000.0,
+            hpf_cutoff: 20.0,
+            lpf_q: BUTTERWORTH_Q,
+            hpf_q: BUTTERWORTH_Q,
+            lpf_resonance: 0.0,
+            hpf_resonance: 0.0,
+            lpf_slope: FilterSlope::Db12,
+            hpf_slope: FilterSlope::Db12,
+            filter_alignment: FilterAlignment::Butterworth,
+            filter_engine: FilterEngine::Biquad,
+            delay_time: 0.0,
+            delay_feedback: 0.0,
+            delay_mix: 0.0,
+            delay_safe_mode: true,
+            delay_mode: StereoDelayMode::Stereo,
+            delay_offset: 0.0,
+            delay_cross_feedback: 0.0,
+            delay_width: 1.0,
+            compressor_threshold: 0.0,
+            compressor_ratio: 1.0,
+            compressor_knee: 6.0,
+            compressor_attack: 10.0,
+            compressor_release: 100.0,
+            compressor_makeup: 0.0,
+            compressor_detector: DetectorMode::Peak,
+            reverb_mix: 0.0,
+            reverb_room_size: 0.5,
+            reverb_decay: 1.5,
+            reverb_damping: 0.5,
+            reverb_pre_delay: 0.02,
+            reverb_diffusion: 0.7,
+            distortion: 0.0,
+            distortion_curve: WaveshaperCurve::SoftClip,
+            distortion_oversampling: DEFAULT_OVERSAMPLING,
+            limiter_ceiling: -0.5,
+            limiter_release: 50.0,
+            limiter_lookahead: 1.5,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl ProcessorParams {
+    // Defaults are a clean pass-through (same as the "clean" preset)
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> ProcessorParams {
+        ProcessorParams::default()
+    }
+
+    // Level that opens the noise gate, in dBFS (-100 leaves it open)
+    #[wasm_bindgen(getter)]
+    pub fn gate_threshold(&self) -> f32 {
+        self.gate_threshold
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_gate_threshold(&mut self, value: f32) {
+        self.gate_threshold = validate(value, self.gate_threshold, GATE_THRESHOLD_RANGE);
+    }
+
+    // How far below the threshold the level must fall to close the gate, in dB
+    #[wasm_bindgen(getter)]
+    pub fn gate_hysteresis(&self) -> f32 {
+        self.gate_hysteresis
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_gate_hysteresis(&mut self, value: f32) {
+        self.gate_hysteresis = validate(value, self.gate_hysteresis, GATE_HYSTERESIS_RANGE);
+    }
+
+    // Gate open/hold/close times, in milliseconds
+    #[wasm_bindgen(getter)]
+    pub fn gate_attack(&self) -> f32 {
+        self.gate_attack
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_gate_attack(&mut self, value: f32) {
+        self.gate_attack = validate(value, self.gate_attack, GATE_ATTACK_RANGE);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn gate_hold(&self) -> f32 {
+        self.gate_hold
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_gate_hold(&mut self, value: f32) {
+        self.gate_hold = validate(value, self.gate_hold, GATE_HOLD_RANGE);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn gate_release(&self) -> f32 {
+        self.gate_release
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_gate_release(&mut self, value: f32) {
+        self.gate_release = validate(value, self.gate_release, GATE_RELEASE_RANGE);
+    }
+
+    // Attenuation while the gate is closed, in dB
+    #[wasm_bindgen(getter)]
+    pub fn gate_range(&self) -> f32 {
+        self.gate_range
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_gate_range(&mut self, value: f32) {
+        self.gate_range = validate(value, self.gate_range, GATE_RANGE_RANGE);
+    }
+
+    // High-pass on the gate detector in Hz so rumble does not open it (0 = off)
+    #[wasm_bindgen(getter)]
+    pub fn gate_sidechain_hpf(&self) -> f32 {
+        self.gate_sidechain_hpf
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_gate_sidechain_hpf(&mut self, value: f32) {
+        if value <= 0.0 {
+            self.gate_sidechain_hpf = 0.0;
+        } else {
+            self.gate_sidechain_hpf = validate(value, self.gate_sidechain_hpf, GATE_SIDECHAIN_RANGE);
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_gain(&mut self, value: f32) {
+        self.gain = validate(value, self.gain, GAIN_RANGE);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn lpf_cutoff(&self) -> f32 {
+        self.lpf_cutoff
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_lpf_cutoff(&mut self, value: f32) {
+        self.lpf_cutoff = validate(value, self.lpf_cutoff, CUTOFF_RANGE);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hpf_cutoff(&self) -> f32 {
+        self.hpf_cutoff
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_hpf_cutoff(&mut self, value: f32) {
+        self.hpf_cutoff = validate(value, self.hpf_cutoff, CUTOFF_RANGE);
+    }
+
+    // Filter resonance - 0.707 is a flat Butterworth response
+    #[wasm_bindgen(getter)]
+    pub fn lpf_q(&self) -> f32 {
+        self.lpf_q
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_lpf_q(&mut self, value: f32) {
+        self.lpf_q = validate(value, self.lpf_q, Q_RANGE);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hpf_q(&self) -> f32 {
+        self.hpf_q
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_hpf_q(&mut self, value: f32) {
+        self.hpf_q = validate(value, self.hpf_q, Q_RANGE);
+    }
+
+    // State-variable engine only: 0..1 resonance that overrides the Q
+    // (1 self-oscillates); 0 leaves the Q in charge
+    #[wasm_bindgen(getter)]
+    pub fn lpf_resonance(&self) -> f32 {
+        self.lpf_resonance
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_lpf_resonance(&mut self, value: f32) {
+        self.lpf_resonance = validate(value, self.lpf_resonance, UNIT_RANGE);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hpf_resonance(&self) -> f32 {
+        self.hpf_resonance
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_hpf_resonance(&mut self, value: f32) {
+        self.hpf_resonance = validate(value, self.hpf_resonance, UNIT_RANGE);
+    }
+
+    // Roll-off of each filter (cascaded biquad sections)
+    #[wasm_bindgen(getter)]
+    pub fn lpf_slope(&self) -> FilterSlope {
+        self.lpf_slope
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_lpf_slope(&mut self, value: FilterSlope) {
+        self.lpf_slope = value;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hpf_slope(&self) -> FilterSlope {
+        self.hpf_slope
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_hpf_slope(&mut self, value: FilterSlope) {
+        self.hpf_slope = value;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn filter_alignment(&self) -> FilterAlignment {
+        self.filter_alignment
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_filter_alignment(&mut self, value: FilterAlignment) {
+        self.filter_alignment = value;
+    }
+
+    // State-variable engine is stable under fast cutoff sweeps
+    #[wasm_bindgen(getter)]
+    pub fn filter_engine(&self) -> FilterEngine {
+        self.filter_engine
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_filter_engine(&mut self, value: FilterEngine) {
+        self.filter_engine = value;
+    }
+
+    // Delay time in seconds (the delay buffer holds one second)
+    #[wasm_bindgen(getter)]
+    pub fn delay_time(&self) -> f32 {
+        self.delay_time
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_delay_time(&mut self, value: f32) {
+        self.delay_time = validate(value, self.delay_time, DELAY_TIME_RANGE);
+    }
+
+    // Feedback 0..1 is the true loop gain - 1.0 repeats forever
+    #[wasm_bindgen(getter)]
+    pub fn delay_feedback(&self) -> f32 {
+        self.delay_feedback
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_delay_feedback(&mut self, value: f32) {
+        self.delay_feedback = validate(value, self.delay_feedback, UNIT_RANGE);
+    }
+
+    // Dry/wet balance with an equal-power law (0.5 = both at -3 dB)
+    #[wasm_bindgen(getter)]
+    pub fn delay_mix(&self) -> f32 {
+        self.delay_mix
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_delay_mix(&mut self, value: f32) {
+        self.delay_mix = validate(value, self.delay_mix, UNIT_RANGE);
+    }
+
+    // Safe mode soft-saturates the feedback path so high feedback
+    // self-oscillates at a bounded level instead of running away
+    #[wasm_bindgen(getter)]
+    pub fn delay_safe_mode(&self) -> bool {
+        self.delay_safe_mode
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_delay_safe_mode(&mut self, value: bool) {
+        self.delay_safe_mode = value;
+    }
+
+    // Stereo (left to left, right to right) or ping-pong
+    #[wasm_bindgen(getter)]
+    pub fn delay_mode(&self) -> StereoDelayMode {
+        self.delay_mode
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_delay_mode(&mut self, value: StereoDelayMode) {
+        self.delay_mode = value;
+    }
+
+    // Seconds added to the right delay time (negative makes it shorter)
+    #[wasm_bindgen(getter)]
+    pub fn delay_offset(&self) -> f32 {
+        self.delay_offset
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_delay_offset(&mut self, value: f32) {
+        self.delay_offset = validate(value, self.delay_offset, DELAY_OFFSET_RANGE);
+    }
+
+    // Share of each repeat sent to the opposite side (stereo mode)
+    #[wasm_bindgen(getter)]
+    pub fn delay_cross_feedback(&self) -> f32 {
+        self.delay_cross_feedback
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_delay_cross_feedback(&mut self, value: f32) {
+        self.delay_cross_feedback = validate(value, self.delay_cross_feedback, UNIT_RANGE);
+    }
+
+    // Stereo width of the echoes - 0 is centred, 1 is full width
+    #[wasm_bindgen(getter)]
+    pub fn delay_width(&self) -> f32 {
+        self.delay_width
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_delay_width(&mut self, value: f32) {
+        self.delay_width = validate(value, self.delay_width, UNIT_RANGE);
+    }
+
+    // Level above which the compressor acts, in dBFS
+    #[wasm_bindgen(getter)]
+    pub fn compressor_threshold(&self) -> f32 {
+        self.compressor_threshold
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_compressor_threshold(&mut self, value: f32) {
+        self.compressor_threshold = validate(value, self.compressor_threshold, COMPRESSOR_THRESHOLD_RANGE);
+    }
+
+    // Input dB over the threshold per output dB - 1 leaves the signal alone
+    #[wasm_bindgen(getter)]
+    pub fn compressor_ratio(&self) -> f32 {
+        self.compressor_ratio
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_compressor_ratio(&mut self, value: f32) {
+        self.compressor_ratio = validate(value, self.compressor_ratio, COMPRESSOR_RATIO_RANGE);
+    }
+
+    // Soft-knee width around the threshold, in dB
+    #[wasm_bindgen(getter)]
+    pub fn compressor_knee(&self) -> f32 {
+        self.compressor_knee
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_compressor_knee(&mut self, value: f32) {
+        self.compressor_knee = validate(value, self.compressor_knee, COMPRESSOR_KNEE_RANGE);
+    }
+
+    // Gain reduction attack and release times, in milliseconds
+    #[wasm_bindgen(getter)]
+    pub fn compressor_attack(&self) -> f32 {
+        self.compressor_attack
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_compressor_attack(&mut self, value: f32) {
+        self.compressor_attack = validate(value, self.compressor_attack, COMPRESSOR_ATTACK_RANGE);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn compressor_release(&self) -> f32 {
+        self.compressor_release
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_compressor_release(&mut self, value: f32) {
+        self.compressor_release = validate(value, self.compressor_release, COMPRESSOR_RELEASE_RANGE);
+    }
+
+    // Gain added after compression, in dB
+    #[wasm_bindgen(getter)]
+    pub fn compressor_makeup(&self) -> f32 {
+        self.compressor_makeup
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_compressor_makeup(&mut self, value: f32) {
+        self.compressor_makeup = validate(value, self.compressor_makeup, COMPRESSOR_MAKEUP_RANGE);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn compressor_detector(&self) -> DetectorMode {
+        self.compressor_detector
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_compressor_detector(&mut self, value: DetectorMode) {
+        self.compressor_detector = value;
+    }
+
+    // Reverb dry/wet balance (equal-power); 0 switches the reverb off
+    #[wasm_bindgen(getter)]
+    pub fn reverb_mix(&self) -> f32 {
+        self.reverb_mix
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_reverb_mix(&mut self, value: f32) {
+        self.reverb_mix = validate(value, self.reverb_mix, UNIT_RANGE);
+    }
+
+    // 0 = small room, 1 = large hall (scales the echo density and spacing)
+    #[wasm_bindgen(getter)]
+    pub fn reverb_room_size(&self) -> f32 {
+        self.reverb_room_size
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_reverb_room_size(&mut self, value: f32) {
+        self.reverb_room_size = validate(value, self.reverb_room_size, UNIT_RANGE);
+    }
+
+    // Time for the tail to fall by 60 dB, in seconds
+    #[wasm_bindgen(getter)]
+    pub fn reverb_decay(&self) -> f32 {
+        self.reverb_decay
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_reverb_decay(&mut self, value: f32) {
+        self.reverb_decay = validate(value, self.reverb_decay, REVERB_DECAY_RANGE);
+    }
+
+    // How much faster high frequencies die away (0 = bright, 1 = dark)
+    #[wasm_bindgen(getter)]
+    pub fn reverb_damping(&self) -> f32 {
+        self.reverb_damping
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_reverb_damping(&mut self, value: f32) {
+        self.reverb_damping = validate(value, self.reverb_damping, UNIT_RANGE);
+    }
+
+    // Gap before the reverb starts, in seconds
+    #[wasm_bindgen(getter)]
+    pub fn reverb_pre_delay(&self) -> f32 {
+        self.reverb_pre_delay
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_reverb_pre_delay(&mut self, value: f32) {
+        self.reverb_pre_delay = validate(value, self.reverb_pre_delay, REVERB_PRE_DELAY_RANGE);
+    }
+
+    // Smears the onset - low values keep distinct early echoes
+    #[wasm_bindgen(getter)]
+    pub fn reverb_diffusion(&self) -> f32 {
+        self.reverb_diffusion
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_reverb_diffusion(&mut self, value: f32) {
+        self.reverb_diffusion = validate(value, self.reverb_diffusion, UNIT_RANGE);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn distortion(&self) -> f32 {
+        self.distortion
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_distortion(&mut self, value: f32) {
+        self.distortion = validate(value, self.distortion, UNIT_RANGE);
+    }
+
+    // Shape of the distortion stage (Table falls back to SoftClip - tables
+    // need a Waveshaper effect)
+    #[wasm_bindgen(getter)]
+    pub fn distortion_curve(&self) -> WaveshaperCurve {
+        self.distortion_curve
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_distortion_curve(&mut self, value: WaveshaperCurve) {
+        self.distortion_curve = if value == WaveshaperCurve::Table { WaveshaperCurve::SoftClip } else { value };
+    }
+
+    // Rate the distortion runs at to keep its harmonics from aliasing;
+    // higher ratios add a little latency (X1 = none)
+    #[wasm_bindgen(getter)]
+    pub fn distortion_oversampling(&self) -> OversamplingFactor {
+        self.distortion_oversampling
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_distortion_oversampling(&mut self, value: OversamplingFactor) {
+        self.distortion_oversampling = value;
+    }
+
+    // Highest output level of the final limiter, in dBFS (true peak)
+    #[wasm_bindgen(getter)]
+    pub fn limiter_ceiling(&self) -> f32 {
+        self.limiter_ceiling
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_limiter_ceiling(&mut self, value: f32) {
+        self.limiter_ceiling = validate(value, self.limiter_ceiling, LIMITER_CEILING_RANGE);
+    }
+
+    // Limiter recovery time, in milliseconds
+    #[wasm_bindgen(getter)]
+    pub fn limiter_release(&self) -> f32 {
+        self.limiter_release
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_limiter_release(&mut self, value: f32) {
+        self.limiter_release = validate(value, self.limiter_release, LIMITER_RELEASE_RANGE);
+    }
+
+    // How far the limiter looks ahead, in milliseconds - this is also its latency
+    #[wasm_bindgen(getter)]
+    pub fn limiter_lookahead(&self) -> f32 {
+        self.limiter_lookahead
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_limiter_lookahead(&mut self, value: f32) {
+        self.limiter_lookahead = validate(value, self.limiter_lookahead, LIMITER_LOOKAHEAD_RANGE);
+    }
+}
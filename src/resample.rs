@@ -0,0 +1,51 @@
+use std::f32::consts::PI;
+
+// Zero crossings of the sinc kernel on each side
+const KERNEL_HALF_WIDTH: usize = 16;
+
+// Offline windowed-sinc sample rate conversion, for material loaded from JS
+// (impulse responses, samples) rather than the live signal
+// Downsampling lowers the kernel cutoff so nothing aliases
+pub fn resample(input: &[f32], from_rate: f32, to_rate: f32) -> Vec<f32> {
+    if input.is_empty() || !from_rate.is_finite() || !to_rate.is_finite() || from_rate <= 0.0 || to_rate <= 0.0 {
+        return input.to_vec();
+    }
+    if (from_rate - to_rate).abs() < 1e-3 {
+        return input.to_vec();
+    }
+
+    let ratio = from_rate as f64 / to_rate as f64;
+    let output_len = ((input.len() as f64) / ratio).ceil() as usize;
+    // Cutoff relative to the input Nyquist
+    let cutoff = (to_rate / from_rate).min(1.0);
+    let half_width = KERNEL_HALF_WIDTH as f32 / cutoff;
+
+    (0..output_len)
+        .map(|i| {
+            let position = i as f64 * ratio;
+            let centre = position.floor() as isize;
+            let frac = (position - centre as f64) as f32;
+            let reach = half_width.ceil() as isize;
+            let mut sum = 0.0;
+            for j in (centre - reach + 1)..=(centre + reach) {
+                if j < 0 || j as usize >= input.len() {
+                    continue;
+                }
+                let t = (j - centre) as f32 - frac;
+                sum += input[j as usize] * kernel(t, cutoff, half_width);
+            }
+            sum
+        })
+        .collect()
+}
+
+// Blackman-windowed sinc at distance t (input samples)
+fn kernel(t: f32, cutoff: f32, half_width: f32) -> f32 {
+    if t.abs() >= half_width {
+        return 0.0;
+    }
+    let x = PI * t * cutoff;
+    let sinc = if x.abs() < 1e-6 { 1.0 } else { x.sin() / x };
+    let window = 0.42 + 0.5 * (PI * t / half_width).cos() + 0.08 * (2.0 * PI * t / half_width).cos();
+    cutoff * sinc * window
+}
@@ -0,0 +1,161 @@
+use wasm_bindgen::prelude::*;
+
+// How a smoothed parameter approaches its target
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SmoothingMode {
+    // Exponential approach, time is the time constant (~63% of the way)
+    OnePole,
+    // Straight line, time is the full ramp length
+    Linear,
+}
+
+pub const DEFAULT_SMOOTHING_MS: f32 = 20.0;
+
+// Per-sample coefficient of a one-pole with the given time constant
+pub fn one_pole(time_ms: f32, sample_rate: f32) -> f32 {
+    (-1.0 / (time_ms * 0.001 * sample_rate).max(1.0)).exp()
+}
+
+pub fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+// Floored at -200 dB so silence stays finite
+pub fn gain_to_db(gain: f32) -> f32 {
+    20.0 * gain.max(1e-10).log10()
+}
+
+// A single parameter that glides towards its target one sample at a time
+#[derive(Clone, Copy, Debug)]
+pub struct SmoothedValue {
+    current: f32,
+    target: f32,
+    mode: SmoothingMode,
+    ramp_samples: usize,
+    coeff: f32,
+    step: f32,
+    steps_left: usize,
+}
+
+impl SmoothedValue {
+    pub fn new(value: f32) -> SmoothedValue {
+        SmoothedValue {
+            current: value,
+            target: value,
+            mode: SmoothingMode::Linear,
+            ramp_samples: 0,
+            coeff: 0.0,
+            step: 0.0,
+            steps_left: 0,
+        }
+    }
+
+    pub fn configure(&mut self, mode: SmoothingMode, time_ms: f32, sample_rate: f32) {
+        let samples = (time_ms.max(0.0) * 0.001 * sample_rate).round();
+        self.mode = mode;
+        self.ramp_samples = samples as usize;
+        self.coeff = if samples > 0.0 { (-1.0 / samples).exp() } else { 0.0 };
+        // Finish any ramp in flight at the new speed
+        let target = self.target;
+        self.target = self.current;
+        self.set_target(target);
+    }
+
+    pub fn set_target(&mut self, target: f32) {
+        if target == self.target {
+            return;
+        }
+        self.target = target;
+        if self.ramp_samples == 0 {
+            self.snap(target);
+        } else if self.mode == SmoothingMode::Linear {
+            self.steps_left = self.ramp_samples;
+            self.step = (target - self.current) / self.ramp_samples as f32;
+        }
+    }
+
+    // Jump straight to a value, cancelling any ramp
+    pub fn snap(&mut self, value: f32) {
+        self.current = value;
+        self.target = value;
+        self.steps_left = 0;
+    }
+
+    pub fn tick(&mut self) -> f32 {
+        if self.current == self.target {
+            return self.current;
+        }
+        match self.mode {
+            SmoothingMode::Linear => {
+                if self.steps_left > 1 {
+                    self.steps_left -= 1;
+                    self.current += self.step;
+                } else {
+                    self.steps_left = 0;
+                    self.current = self.target;
+                }
+            }
+            SmoothingMode::OnePole => {
+                self.current = self.target + (self.current - self.target) * self.coeff;
+                if (self.target - self.current).abs() <= 1e-6 * self.target.abs().max(1.0) {
+                    self.current = self.target;
+                }
+            }
+        }
+        self.current
+    }
+
+    pub fn is_smoothing(&self) -> bool {
+        self.current != self.target
+    }
+
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+}
+
+// Biquad coefficients [b0, b1, b2, a1, a2] interpolated per sample
+// so a cutoff change never makes the filter jump
+#[derive(Clone, Copy, Debug)]
+pub struct SmoothedCoeffs {
+    values: [SmoothedValue; 5],
+}
+
+impl SmoothedCoeffs {
+    pub fn new(coeffs: [f32; 5]) -> SmoothedCoeffs {
+        SmoothedCoeffs {
+            values: coeffs.map(SmoothedValue::new),
+        }
+    }
+
+    pub fn configure(&mut self, mode: SmoothingMode, time_ms: f32, sample_rate: f32) {
+        for v in self.values.iter_mut() {
+            v.configure(mode, time_ms, sample_rate);
+        }
+    }
+
+    pub fn set_target(&mut self, coeffs: [f32; 5]) {
+        for (v, c) in self.values.iter_mut().zip(coeffs) {
+            v.set_target(c);
+        }
+    }
+
+    pub fn snap(&mut self, coeffs: [f32; 5]) {
+        for (v, c) in self.values.iter_mut().zip(coeffs) {
+            v.snap(c);
+        }
+    }
+
+    pub fn tick(&mut self) -> [f32; 5] {
+        let mut out = [0.0; 5];
+        for (o, v) in out.iter_mut().zip(self.values.iter_mut()) {
+            *o = v.tick();
+        }
+        out
+    }
+}
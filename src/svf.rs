@@ -0,0 +1,299 @@
+use wasm_bindgen::prelude::*;
+
+use crate::biquad::{FilterType, BUTTERWORTH_Q};
+use crate::cascade::{section_qs, FilterAlignment, FilterSlope, MAX_SECTIONS};
+use crate::smoothing::{SmoothedValue, SmoothingMode};
+
+// Which filter implementation the processor's LPF/HPF use
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterEngine {
+    // Direct-form-I cookbook biquads
+    Biquad,
+    // Zero-delay-feedback state-variable filter, safe to sweep per sample
+    StateVariable,
+}
+
+impl FilterEngine {
+    pub fn from_index(index: usize) -> Option<FilterEngine> {
+        [FilterEngine::Biquad, FilterEngine::StateVariable].get(index).copied()
+    }
+}
+
+// All responses of one SVF tick
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SvfOutput {
+    pub low: f32,
+    pub band: f32,
+    pub high: f32,
+    pub notch: f32,
+}
+
+impl SvfOutput {
+    // Map a cookbook response onto the SVF outputs (k = 1/Q damping)
+    pub fn select(&self, filter_type: FilterType, k: f32) -> f32 {
+        match filter_type {
+            FilterType::LowPass => self.low,
+            FilterType::HighPass => self.high,
+            FilterType::BandPass => self.band,
+            FilterType::BandPassPeak => self.band * k,
+            FilterType::Notch => self.notch,
+            FilterType::AllPass => self.low + self.high - k * self.band,
+            // Gain-based responses have no direct SVF tap; fall back to low-pass
+            FilterType::Peaking | FilterType::LowShelf | FilterType::HighShelf => self.low,
+        }
+    }
+}
+
+// Topology-preserving-transform SVF (Zavalishin / Simper)
+// The trapezoidal integrators keep it stable however fast the cutoff moves
+#[derive(Clone, Debug)]
+pub struct StateVariableFilter {
+    sample_rate: f32,
+    cutoff: SmoothedValue,
+    k: f32,
+    g: f32,
+    g_cutoff: f32,
+    ic1eq: f32,
+    ic2eq: f32,
+}
+
+impl StateVariableFilter {
+    pub fn new(cutoff: f32, sample_rate: f32) -> StateVariableFilter {
+        let mut svf = StateVariableFilter {
+            sample_rate,
+            cutoff: SmoothedValue::new(cutoff),
+            k: 1.0 / BUTTERWORTH_Q,
+            g: 0.0,
+            g_cutoff: -1.0,
+            ic1eq: 0.0,
+            ic2eq: 0.0,
+        };
+        svf.g = svf.compute_g(cutoff);
+        svf
+    }
+
+    pub fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        self.cutoff.configure(mode, time_ms, self.sample_rate);
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.g_cutoff = -1.0;
+    }
+
+    // Cutoff in Hz - glides per sample using the smoothing settings
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        if cutoff.is_finite() {
+            self.cutoff.set_target(cutoff);
+        }
+    }
+
+    pub fn snap_cutoff(&mut self, cutoff: f32) {
+        if cutoff.is_finite() {
+            self.cutoff.snap(cutoff);
+        }
+    }
+
+    // Advance the cutoff glide by one sample and return it
+    #[inline]
+    pub fn tick_cutoff(&mut self) -> f32 {
+        self.cutoff.tick()
+    }
+
+    pub fn cutoff(&self) -> f32 {
+        self.cutoff.current()
+    }
+
+    pub fn set_q(&mut self, q: f32) {
+        if q.is_finite() && q > 0.0 {
+            self.k = 1.0 / q;
+        }
+    }
+
+    // Resonance 0..1 - 0 is heavily damped, 1 self-oscillates
+    pub fn set_resonance(&mut self, resonance: f32) {
+        if resonance.is_finite() {
+            self.k = 2.0 * (1.0 - resonance.clamp(0.0, 1.0));
+        }
+    }
+
+    pub fn damping(&self) -> f32 {
+        self.k
+    }
+
+    // Tick at the smoothed cutoff
+    #[inline]
+    pub fn process_sample(&mut self, input: f32) -> SvfOutput {
+        let cutoff = self.cutoff.tick();
+        if cutoff != self.g_cutoff {
+            self.g = self.compute_g(cutoff);
+            self.g_cutoff = cutoff;
+        }
+        self.tick(input, self.g)
+    }
+
+    // Tick at an explicit cutoff, for audio-rate modulation (auto-wah, envelope filter)
+    #[inline]
+    pub fn process_sample_with_cutoff(&mut self, input: f32, cutoff: f32) -> SvfOutput {
+        let g = self.compute_g(cutoff);
+        self.tick(input, g)
+    }
+
+    pub fn reset(&mut self) {
+        self.ic1eq = 0.0;
+        self.ic2eq = 0.0;
+    }
+
+    fn compute_g(&self, cutoff: f32) -> f32 {
+        let cutoff = cutoff.clamp(10.0, self.sample_rate * 0.49);
+        (std::f32::consts::PI * cutoff / self.sample_rate).tan()
+    }
+
+    #[inline]
+    fn tick(&mut self, v0: f32, g: f32) -> SvfOutput {
+        let a1 = 1.0 / (1.0 + g * (g + self.k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+        let v3 = v0 - self.ic2eq;
+        let v1 = a1 * self.ic1eq + a2 * v3;
+        let v2 = self.ic2eq + a2 * self.ic1eq + a3 * v3;
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+        let high = v0 - self.k * v1 - v2;
+        SvfOutput {
+            low: v2,
+            band: v1,
+            high,
+            notch: v2 + high,
+        }
+    }
+}
+
+// SVF filter with the same slopes and alignments as CascadedFilter
+// Every stage computes all outputs, so switching between low-, high-,
+// band-pass and notch is instant and keeps the filter state
+#[derive(Clone, Debug)]
+pub struct SvfCascade {
+    filter_type: FilterType,
+    stages: Vec<StateVariableFilter>,
+    active: usize,
+    slope: FilterSlope,
+    alignment: FilterAlignment,
+    q: f32,
+    // 0..1 on the sharpest stage, overriding q; 0 = off
+    resonance: f32,
+}
+
+impl SvfCascade {
+    pub fn new(filter_type: FilterType, cutoff: f32, sample_rate: f32) -> SvfCascade {
+        let mut cascade = SvfCascade {
+            filter_type,
+            stages: (0..MAX_SECTIONS).map(|_| StateVariableFilter::new(cutoff, sample_rate)).collect(),
+            active: 1,
+            slope: FilterSlope::Db12,
+            alignment: FilterAlignment::Butterworth,
+            q: BUTTERWORTH_Q,
+            resonance: 0.0,
+        };
+        cascade.retune();
+        cascade
+    }
+
+    pub fn set_smoothing(&mut self, mode: SmoothingMode, time_ms: f32) {
+        for s in self.stages.iter_mut() {
+            s.set_smoothing(mode, time_ms);
+        }
+    }
+
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        for s in self.stages.iter_mut() {
+            s.set_cutoff(cutoff);
+        }
+    }
+
+    pub fn snap_cutoff(&mut self, cutoff: f32) {
+        for s in self.stages.iter_mut() {
+            s.snap_cutoff(cutoff);
+        }
+    }
+
+    pub fn set_type(&mut self, filter_type: FilterType) {
+        self.filter_type = filter_type;
+    }
+
+    pub fn set_q(&mut self, q: f32) {
+        if q.is_finite() && q > 0.0 && q != self.q {
+            self.q = q;
+            self.retune();
+        }
+    }
+
+    // Resonance 0..1 of the sharpest stage (1 self-oscillates); 0 hands back to q
+    pub fn set_resonance(&mut self, resonance: f32) {
+        if resonance.is_finite() && resonance != self.resonance {
+            self.resonance = resonance.clamp(0.0, 1.0);
+            self.retune();
+        }
+    }
+
+    pub fn set_slope(&mut self, slope: FilterSlope) {
+        if slope != self.slope {
+            self.slope = slope;
+            self.retune();
+        }
+    }
+
+    pub fn set_alignment(&mut self, alignment: FilterAlignment) {
+        if alignment != self.alignment {
+            self.alignment = alignment;
+            self.retune();
+        }
+    }
+
+    #[inline]
+    pub fn process_sample(&mut self, input: f32) -> f32 {
+        let mut x = input;
+        for s in self.stages[..self.active].iter_mut() {
+            let k = s.damping();
+            x = s.process_sample(x).select(self.filter_type, k);
+        }
+        x
+    }
+
+    // Tick with every stage's gliding cutoff multiplied by `scale`, for
+    // per-sample sweeps (envelope filter, auto-wah)
+    #[inline]
+    pub fn process_sample_scaled(&mut self, input: f32, scale: f32) -> f32 {
+        let mut x = input;
+        for s in self.stages[..self.active].iter_mut() {
+            let k = s.damping();
+            let cutoff = s.tick_cutoff() * scale;
+            x = s.process_sample_with_cutoff(x, cutoff).select(self.filter_type, k);
+        }
+        x
+    }
+
+    pub fn reset(&mut self) {
+        for s in self.stages.iter_mut() {
+            s.reset();
+        }
+    }
+
+    fn retune(&mut self) {
+        let qs = section_qs(self.slope, self.alignment);
+        let scale = self.q / BUTTERWORTH_Q;
+        let last = qs.len() - 1;
+        for (i, (stage, &q)) in self.stages.iter_mut().zip(qs.iter()).enumerate() {
+            if i == last && self.resonance > 0.0 {
+                stage.set_resonance(self.resonance);
+            } else {
+                stage.set_q(if i == last { q * scale } else { q });
+            }
+            if i >= self.active {
+                stage.reset();
+            }
+        }
+        self.active = qs.len();
+    }
+}
@@ -0,0 +1,332 @@
+use std::f32::consts::PI;
+
+use wasm_bindgen::prelude::*;
+
+use crate::fft::{Complex, RealFft};
+use crate::resample::resample;
+use crate::window::{fill_window, WindowType};
+
+// Output duration relative to the input (2 = twice as long)
+pub const STRETCH_RANGE: (f32, f32) = (0.25, 4.0);
+// Frequency ratio of the pitch shift (2 = up an octave)
+pub const PITCH_RATIO_RANGE: (f32, f32) = (0.25, 4.0);
+
+// Analysis frame length in ms, rounded up to a power of two
+const FRAME_MS: f32 = 40.0;
+// Synthesis frames overlap by this factor
+const OVERLAP: usize = 4;
+// A peak is the largest bin within this many bins either side
+const PEAK_REACH: usize = 2;
+// Peaks more than 80 dB below the loudest bin of the frame are ignored
+const PEAK_FLOOR: f32 = 1e-4;
+// Onset detection frames are this much shorter than the vocoder's, to time
+// the attacks more closely
+const DETECTION_DIVISION: usize = 4;
+// Rise in normalised spectral flux over its recent mean that marks a transient
+const ONSET_THRESHOLD: f32 = 0.2;
+// Detection frames averaged for that mean
+const ONSET_HISTORY: usize = 16;
+// Mean bin magnitude under which a detection frame counts as silence (about -66 dBFS)
+const ONSET_FLOOR: f32 = 1e-3;
+
+// Offline time stretching and pitch shifting of a recorded clip
+// `stretch` scales the duration and `pitch` the frequencies, independently
+// The clip is stretched by stretch * pitch with a phase vocoder, then
+// resampled by 1 / pitch, so pitch shifting moves formants with the pitch
+// Out-of-range ratios are clamped; non-finite ones count as 1
+#[wasm_bindgen]
+pub fn phase_vocoder(input: Vec<f32>, sample_rate: f32, stretch: f32, pitch: f32) -> Vec<f32> {
+    let stretch = if stretch.is_finite() { stretch.clamp(STRETCH_RANGE.0, STRETCH_RANGE.1) } else { 1.0 };
+    let pitch = if pitch.is_finite() { pitch.clamp(PITCH_RATIO_RANGE.0, PITCH_RATIO_RANGE.1) } else { 1.0 };
+    if input.is_empty() || !sample_rate.is_finite() || sample_rate <= 0.0 || (stretch == 1.0 && pitch == 1.0) {
+        return input;
+    }
+
+    let output_len = (input.len() as f64 * stretch as f64).round() as usize;
+    let ratio = stretch * pitch;
+    let stretched = if (ratio - 1.0).abs() < 1e-6 {
+        input
+    } else {
+        let size = ((FRAME_MS * 0.001 * sample_rate).ceil() as usize).next_power_of_two().max(64);
+        Vocoder::new(size).stretch(&input, ratio)
+    };
+    // Played back `pitch` times faster, the stretched clip lands on the wanted duration
+    let mut output = resample(&stretched, sample_rate * pitch, sample_rate);
+    output.resize(output_len, 0.0);
+    output
+}
+
+// Phase vocoder with identity phase locking and transient preservation
+//
+// Frames are written every hop and read from wherever the time map puts
+// them, about every hop / ratio input samples. Each spectral peak's phase
+// advances at the peak's measured frequency; the bins around it keep their
+// analysis phase offsets from the peak, which holds partials together and
+// avoids the usual "phasiness". Around a detected onset the map runs at 1:1
+// and the phases restart from the analysis, so the attack is rebuilt
+// unsmeared instead of being spread across overlapping frames.
+struct Vocoder {
+    size: usize,
+    hop: usize,
+    fft: RealFft,
+    window: Vec<f32>,
+    frame: Vec<f32>,
+    spectrum: Vec<Complex>,
+    magnitude: Vec<f32>,
+    phase: Vec<f32>,
+    // Analysis phases of the previous frame and the phases last synthesised
+    last_phase: Vec<f32>,
+    synth_phase: Vec<f32>,
+    peaks: Vec<usize>,
+}
+
+impl Vocoder {
+    fn new(size: usize) -> Vocoder {
+        let bins = size / 2 + 1;
+        let mut window = vec![0.0; size];
+        fill_window(WindowType::Hann, &mut window);
+        Vocoder {
+            size,
+            hop: size / OVERLAP,
+            fft: RealFft::new(size),
+            window,
+            frame: vec![0.0; size],
+            spectrum: vec![Complex::ZERO; bins],
+            magnitude: vec![0.0; bins],
+            phase: vec![0.0; bins],
+            last_phase: vec![0.0; bins],
+            synth_phase: vec![0.0; bins],
+            peaks: Vec::new(),
+        }
+    }
+
+    // Windowed spectrum of the frame centred on input sample `centre`
+    // (zero outside the input) into magnitude and phase
+    fn analyse(&mut self, input: &[f32], centre: isize) {
+        let start = centre - (self.size / 2) as isize;
+        for (j, x) in self.frame.iter_mut().enumerate() {
+            let i = start + j as isize;
+            let sample = if i >= 0 { input.get(i as usize).copied().unwrap_or(0.0) } else { 0.0 };
+            *x = sample * self.window[j];
+        }
+        self.fft.forward(&self.frame, &mut self.spectrum);
+        for (k, bin) in self.spectrum.iter().enumerate() {
+            self.magnitude[k] = bin.abs();
+            self.phase[k] = bin.im.atan2(bin.re);
+        }
+    }
+
+    // Synthesis to analysis time map that copies the frames around each
+    // transient 1:1 and stretches the rest harder to make up the length
+    fn time_map(&self, input: &[f32], ratio: f64) -> TimeMap {
+        let len = input.len() as f64;
+        // Every frame that can see the attack, allowing for the detection error
+        let reach = (self.size / 2 + self.size / DETECTION_DIVISION / 2) as f64;
+        // Locked time is capped so the free parts still stretch by at least half the ratio
+        let budget = len * ratio.min(1.0) / 2.0;
+        let mut regions: Vec<(f64, f64)> = Vec::new();
+        let mut locked = 0.0;
+        for onset in onsets(input, self.size / DETECTION_DIVISION) {
+            let (mut from, to) = ((onset as f64 - reach).max(0.0), (onset as f64 + reach).min(len));
+            if let Some(last) = regions.last() {
+                from = from.max(last.1);
+            }
+            if to <= from || locked + to - from > budget {
+                continue;
+            }
+            match regions.last_mut() {
+                Some(last) if last.1 == from => last.1 = to,
+                _ => regions.push((from, to)),
+            }
+            locked += to - from;
+        }
+
+        let slope = (len * ratio - locked) / (len - locked);
+        let mut points = vec![(0.0, 0.0)];
+        let mut output = 0.0;
+        let mut last = 0.0;
+        for &(from, to) in &regions {
+            output += (from - last) * slope;
+            points.push((output, from));
+            output += to - from;
+            points.push((output, to));
+            last = to;
+        }
+        points.push((output + (len - last) * slope, len));
+        TimeMap { points, slope, starts: regions.iter().map(|r| r.0).collect() }
+    }
+
+    // Local maxima of the magnitude spectrum, in bin order
+    fn find_peaks(&mut self) {
+        self.peaks.clear();
+        let floor = self.magnitude.iter().fold(0.0f32, |a, &b| a.max(b)) * PEAK_FLOOR;
+        let bins = self.magnitude.len();
+        for k in 1..bins - 1 {
+            let m = self.magnitude[k];
+            let lo = k.saturating_sub(PEAK_REACH);
+            let hi = (k + PEAK_REACH).min(bins - 1);
+            if m > floor && (lo..=hi).all(|j| j == k || self.magnitude[j] < m) {
+                self.peaks.push(k);
+            }
+        }
+    }
+
+    // Synthesis phases for a frame `advance` input samples after the last one
+    fn lock_phases(&mut self, advance: f32) {
+        self.find_peaks();
+        if self.peaks.is_empty() {
+            self.synth_phase.copy_from_slice(&self.phase);
+            return;
+        }
+        let bin_step = 2.0 * PI / self.size as f32;
+        let hop = self.hop as f32;
+        let mut start = 0;
+        for (i, &p) in self.peaks.iter().enumerate() {
+            // Peak phase advances at the peak's true frequency
+            let expected = p as f32 * bin_step;
+            let deviation = wrap_phase(self.phase[p] - self.last_phase[p] - expected * advance);
+            let frequency = expected + deviation / advance;
+            let peak_phase = wrap_phase(self.synth_phase[p] + frequency * hop);
+
+            // Its region runs to the quietest bin before the next peak
+            let end = match self.peaks.get(i + 1) {
+                Some(&next) => (p..next).min_by(|&a, &b| self.magnitude[a].total_cmp(&self.magnitude[b])).unwrap_or(p) + 1,
+                None => self.magnitude.len(),
+            };
+            for k in start..end {
+                self.synth_phase[k] = wrap_phase(peak_phase + self.phase[k] - self.phase[p]);
+            }
+            start = end;
+        }
+    }
+
+    // Input stretched to `ratio` times its length
+    fn stretch(&mut self, input: &[f32], ratio: f32) -> Vec<f32> {
+        let size = self.size;
+        let half = (size / 2) as isize;
+        let bins = self.magnitude.len();
+        let output_len = (input.len() as f64 * ratio as f64).round() as usize;
+        let map = self.time_map(input, ratio as f64);
+        let mut next_transient = 0;
+
+        // Output is offset by one frame so the first frames can start before 0
+        let mut output = vec![0.0; output_len + 2 * size];
+        let mut last_centre = None;
+        let mut m = -((OVERLAP / 2) as isize);
+        while m * (self.hop as isize) < output_len as isize + half {
+            let centre = map.input_at((m * self.hop as isize) as f64).round() as isize;
+            self.analyse(input, centre);
+
+            // The first frame of a locked region restarts from the analysis phases
+            let mut transient = false;
+            while next_transient < map.starts.len() && map.starts[next_transient] <= centre as f64 {
+                transient = true;
+                next_transient += 1;
+            }
+            match last_centre {
+                Some(last) if !transient && centre > last => self.lock_phases((centre - last) as f32),
+                _ => self.synth_phase.copy_from_slice(&self.phase),
+            }
+            self.last_phase.copy_from_slice(&self.phase);
+            last_centre = Some(centre);
+
+            // DC and Nyquist stay real
+            for k in 1..bins - 1 {
+                self.spectrum[k] = Complex::from_angle(self.synth_phase[k]).scale(self.magnitude[k]);
+            }
+            self.fft.inverse(&self.spectrum, &mut self.frame);
+            let start = (m * self.hop as isize - half + size as isize) as usize;
+            for (j, y) in output[start..start + size].iter_mut().enumerate() {
+                *y += self.frame[j] * self.window[j];
+            }
+            m += 1;
+        }
+
+        // Overlapping squared Hann windows sum to a constant
+        let gain = self.hop as f32 / self.window.iter().map(|w| w * w).sum::<f32>();
+        output.drain(..size);
+        output.truncate(output_len);
+        for y in output.iter_mut() {
+            *y *= gain;
+        }
+        output
+    }
+}
+
+// Input positions of transients, from peaks in the spectral flux of
+// `size`-sample frames; each is within half a frame of the attack
+fn onsets(input: &[f32], size: usize) -> Vec<isize> {
+    let half = (size / 2) as isize;
+    let hop = size / OVERLAP;
+    let mut fft = RealFft::new(size);
+    let mut window = vec![0.0; size];
+    fill_window(WindowType::Hann, &mut window);
+    let mut frame = vec![0.0; size];
+    let mut spectrum = vec![Complex::ZERO; size / 2 + 1];
+    let mut last = vec![0.0; spectrum.len()];
+
+    let mut flux = Vec::new();
+    let mut centre = 0;
+    while centre < input.len() as isize + half {
+        for (j, x) in frame.iter_mut().enumerate() {
+            let i = centre - half + j as isize;
+            let sample = if i >= 0 { input.get(i as usize).copied().unwrap_or(0.0) } else { 0.0 };
+            *x = sample * window[j];
+        }
+        fft.forward(&frame, &mut spectrum);
+        let mut total = 0.0;
+        let mut rise = 0.0;
+        for (bin, l) in spectrum.iter().zip(last.iter_mut()) {
+            let m = bin.abs();
+            total += m;
+            rise += (m - *l).max(0.0);
+            *l = m;
+        }
+        flux.push(if total > ONSET_FLOOR * spectrum.len() as f32 { rise / total } else { 0.0 });
+        centre += hop as isize;
+    }
+
+    let mut onsets = Vec::new();
+    for j in 0..flux.len() {
+        let before = if j > 0 { flux[j - 1] } else { 0.0 };
+        let after = flux.get(j + 1).copied().unwrap_or(0.0);
+        let history = &flux[j.saturating_sub(ONSET_HISTORY)..j];
+        let mean = history.iter().sum::<f32>() / history.len().max(1) as f32;
+        if flux[j] >= before && flux[j] > after && flux[j] - mean > ONSET_THRESHOLD {
+            onsets.push((j * hop) as isize);
+        }
+    }
+    onsets
+}
+
+// Piecewise-linear map from output to input time
+struct TimeMap {
+    // (output, input) breakpoints, both increasing
+    points: Vec<(f64, f64)>,
+    // Output samples per input sample outside the locked regions
+    slope: f64,
+    // Input positions where locked regions begin
+    starts: Vec<f64>,
+}
+
+impl TimeMap {
+    fn input_at(&self, output: f64) -> f64 {
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        if output <= first.0 {
+            return first.1 + (output - first.0) / self.slope;
+        }
+        if output >= last.0 {
+            return last.1 + (output - last.0) / self.slope;
+        }
+        let i = self.points.partition_point(|p| p.0 <= output);
+        let (a, b) = (self.points[i - 1], self.points[i]);
+        a.1 + (output - a.0) * (b.1 - a.1) / (b.0 - a.0)
+    }
+}
+
+// Wrap a phase into -pi..pi
+#[inline]
+fn wrap_phase(phase: f32) -> f32 {
+    phase - 2.0 * PI * (phase / (2.0 * PI)).round()
+}
@@ -0,0 +1,72 @@
+use std::f32::consts::PI;
+
+use wasm_bindgen::prelude::*;
+
+// Analysis windows for the FFT
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowType {
+    // No window - exact for bin-centred signals, heavy leakage otherwise
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+    // 4-term, -92 dB sidelobes - good default for spectrum display
+    BlackmanHarris,
+    // Nearly flat main lobe - accurate amplitudes, poor resolution
+    FlatTop,
+}
+
+impl WindowType {
+    pub fn from_index(index: usize) -> Option<WindowType> {
+        [
+            WindowType::Rectangular,
+            WindowType::Hann,
+            WindowType::Hamming,
+            WindowType::Blackman,
+            WindowType::BlackmanHarris,
+            WindowType::FlatTop,
+        ]
+        .get(index)
+        .copied()
+    }
+
+    // Cosine-sum coefficients: w = a0 - a1 cos(x) + a2 cos(2x) - a3 cos(3x) + a4 cos(4x)
+    fn coefficients(self) -> [f32; 5] {
+        match self {
+            WindowType::Rectangular => [1.0, 0.0, 0.0, 0.0, 0.0],
+            WindowType::Hann => [0.5, 0.5, 0.0, 0.0, 0.0],
+            WindowType::Hamming => [0.54, 0.46, 0.0, 0.0, 0.0],
+            WindowType::Blackman => [0.42, 0.5, 0.08, 0.0, 0.0],
+            WindowType::BlackmanHarris => [0.35875, 0.48829, 0.14128, 0.01168, 0.0],
+            WindowType::FlatTop => [0.21557895, 0.41663158, 0.27726316, 0.08357895, 0.006947368],
+        }
+    }
+
+    // Sample i of an n-point periodic window (the DFT-even form used for analysis)
+    pub fn value(self, i: usize, n: usize) -> f32 {
+        let a = self.coefficients();
+        let x = 2.0 * PI * i as f32 / n.max(1) as f32;
+        a[0] - a[1] * x.cos() + a[2] * (2.0 * x).cos() - a[3] * (3.0 * x).cos() + a[4] * (4.0 * x).cos()
+    }
+}
+
+pub fn fill_window(window: WindowType, out: &mut [f32]) {
+    let n = out.len();
+    for (i, w) in out.iter_mut().enumerate() {
+        *w = window.value(i, n);
+    }
+}
+
+// Mean of the window - divide magnitudes by this (times n/2) to read sine amplitudes
+pub fn coherent_gain(window: WindowType) -> f32 {
+    window.coefficients()[0]
+}
+
+// n-point periodic window for JS
+#[wasm_bindgen]
+pub fn window_function(window: WindowType, size: usize) -> Vec<f32> {
+    let mut out = vec![0.0; size];
+    fill_window(window, &mut out);
+    out
+}